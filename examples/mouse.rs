@@ -15,7 +15,7 @@ fn main() {
     while !con.window_closed() {
 
         loop {
-            match input::check_for_event(input::KEY | input::MOUSE) {
+            match input::check_for_event(input::KEY | input::MOUSE | input::FINGER) {
                 None => {
                     break;
                 }
@@ -28,7 +28,13 @@ fn main() {
                             x = mouse_state.cx as i32;
                             y = mouse_state.cy as i32;
                             println!("{:?}", mouse_state);
+                        },
+                        input::Event::Touch(ref touch) => {
+                            x = touch.position.cx as i32;
+                            y = touch.position.cy as i32;
+                            println!("{:?}", touch);
                         }
+                        _ => {}
                     }
                 }
             }
@@ -1,7 +1,9 @@
 #[macro_use]
 extern crate tcod;
 
-use tcod::{Console, RootConsole, BackgroundFlag, TextAlignment};
+use tcod::{RootConsole, BackgroundFlag, TextAlignment};
+use tcod::colors;
+use tcod::console::StyledText;
 
 fn main() {
     let mut root = RootConsole::initializer().size(80, 50).title("Displaying text").init();
@@ -23,6 +25,20 @@ fn main() {
     tcod_print!(root, At(40, 25), Wrap(10, 10), Bg(BackgroundFlag::None), Align(TextAlignment::Center),
                 "This text is printed with every optional parameter, format: {} {}", "string", 1);
 
+    // Multi-colored text is printed by passing a `StyledText` instead of a format string
+    let styled = StyledText::new()
+        .text("Text with ")
+        .fg("red", colors::RED)
+        .text(" and ")
+        .fg("green", colors::GREEN)
+        .text(" words");
+    tcod_print!(root, At(1, 40), Wrap(15, 3), styled);
+
+    // Temporary colors and clipping
+    tcod_print!(root, At(1, 45), Fg(colors::LIGHT_BLUE), BgColor(colors::DARKER_GREY),
+                "Colored text");
+    tcod_print!(root, At(1, 47), Clip(12, 1), Fg(colors::YELLOW), "This line is cut off after twelve cells");
+
     root.flush();
     root.wait_for_keypress(true);
 }
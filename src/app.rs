@@ -0,0 +1,310 @@
+//! A game loop with fixed-timestep updates
+//!
+//! Implement [App](./trait.App.html) and hand it to a [Runner](./struct.Runner.html), which reads
+//! the input events, updates the game in fixed steps (so the simulation runs at the same speed
+//! regardless of the frame rate), renders as often as the frame rate allows and keeps a
+//! [FrameReport](./struct.FrameReport.html):
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use tcod::app::{App, Runner};
+//! use tcod::console::{BackgroundFlag, Console, Root};
+//! use tcod::input::{Event, KeyCode};
+//!
+//! struct Game { x: f32, previous_x: f32, done: bool }
+//!
+//! impl App for Game {
+//!     fn update(&mut self, dt: Duration) {
+//!         self.previous_x = self.x;
+//!         self.x = (self.x + 10.0 * dt.as_secs_f32()).min(79.0);
+//!     }
+//!
+//!     fn render(&mut self, root: &mut Root, alpha: f32) {
+//!         // Draw the position between the last two updates
+//!         let x = self.previous_x + (self.x - self.previous_x) * alpha;
+//!         root.clear();
+//!         root.put_char(x as i32, 25, '@', BackgroundFlag::None);
+//!     }
+//!
+//!     fn handle_event(&mut self, event: Event) {
+//!         if let Event::Key(key) = event {
+//!             self.done |= key.code == KeyCode::Escape;
+//!         }
+//!     }
+//!
+//!     fn is_finished(&self) -> bool {
+//!         self.done
+//!     }
+//! }
+//!
+//! let mut root = Root::initializer().size(80, 50).init();
+//! let mut runner = Runner::new(30);
+//! runner.fps(60);
+//! runner.run(&mut root, &mut Game { x: 0.0, previous_x: 0.0, done: false });
+//! println!("{}", runner.report());
+//! ```
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use console::Root;
+use input::{self, Event, EventFlags, KEY, MOUSE};
+use system;
+
+/// A game driven by a [Runner](./struct.Runner.html)
+pub trait App {
+    /// Advances the game by `dt`. A `Runner` always passes its fixed timestep.
+    fn update(&mut self, dt: Duration);
+
+    /// Draws the game. The runner flushes the console afterwards.
+    ///
+    /// `alpha` is how far the time is into the next update (see `FixedTimestep::alpha`), to
+    /// interpolate between the states of the last two updates.
+    fn render(&mut self, root: &mut Root, alpha: f32);
+
+    /// Handles an input event. The events of a frame are handled before its updates.
+    fn handle_event(&mut self, event: Event) {
+        let _ = event;
+    }
+
+    /// Returns whether the runner should stop. It also stops when the window is closed.
+    fn is_finished(&self) -> bool {
+        false
+    }
+}
+
+/// Splits the elapsed time into steps of a fixed length
+///
+/// The time that doesn't add up to a full step is kept for the next call to `advance`.
+#[derive(Clone, Debug)]
+pub struct FixedTimestep {
+    step: Duration,
+    lag: Duration,
+    max_steps: u32,
+}
+
+impl FixedTimestep {
+    /// Creates a timestep of `step`, allowing at most 5 steps per call to `advance`.
+    pub fn new(step: Duration) -> FixedTimestep {
+        assert!(step > Duration::from_millis(0), "the timestep must not be zero");
+        FixedTimestep { step, lag: Duration::from_millis(0), max_steps: 5 }
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Sets the most steps `advance` returns. When the game falls further behind (e.g. after the
+    /// window was dragged around), the rest of the time is dropped instead of trying to catch up
+    /// with ever longer frames.
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        assert!(max_steps > 0);
+        self.max_steps = max_steps;
+    }
+
+    /// Adds `elapsed` to the time waiting to be simulated and returns how many steps to run.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.lag += elapsed;
+        let mut steps = 0;
+        while self.lag >= self.step {
+            if steps == self.max_steps {
+                self.lag = Duration::from_millis(0);
+                break;
+            }
+            self.lag -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Returns how far the time is into the next step, between 0.0 and 1.0. Rendering can use
+    /// it to interpolate between the last two states.
+    pub fn alpha(&self) -> f32 {
+        self.lag.as_secs_f32() / self.step.as_secs_f32()
+    }
+}
+
+/// Frame timing statistics
+#[derive(Clone, Debug, Default)]
+pub struct FrameReport {
+    frames: u64,
+    updates: u64,
+    total: Duration,
+    last: Duration,
+    worst: Duration,
+}
+
+impl FrameReport {
+    pub fn new() -> FrameReport {
+        FrameReport::default()
+    }
+
+    /// Records a frame that took `length` and ran `updates` updates.
+    pub fn record(&mut self, length: Duration, updates: u32) {
+        self.frames += 1;
+        self.updates += updates as u64;
+        self.total += length;
+        self.last = length;
+        self.worst = self.worst.max(length);
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    pub fn last_frame(&self) -> Duration {
+        self.last
+    }
+
+    pub fn worst_frame(&self) -> Duration {
+        self.worst
+    }
+
+    pub fn average_frame(&self) -> Duration {
+        if self.frames == 0 {
+            Duration::from_millis(0)
+        } else {
+            self.total / self.frames as u32
+        }
+    }
+
+    /// Returns the average number of frames per second.
+    pub fn fps(&self) -> f32 {
+        if self.total == Duration::from_millis(0) {
+            0.0
+        } else {
+            self.frames as f32 / self.total.as_secs_f32()
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = FrameReport::default();
+    }
+}
+
+impl fmt::Display for FrameReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} frames ({:.1} fps), {} updates, average frame {:.2} ms, worst {:.2} ms",
+               self.frames, self.fps(), self.updates,
+               self.average_frame().as_secs_f64() * 1000.0,
+               self.worst.as_secs_f64() * 1000.0)
+    }
+}
+
+/// Runs an [App](./trait.App.html) until it's finished or the window is closed
+pub struct Runner {
+    timestep: FixedTimestep,
+    fps: Option<i32>,
+    event_mask: EventFlags,
+    report: FrameReport,
+}
+
+impl Runner {
+    /// Creates a runner updating the game `updates_per_second` times per second.
+    pub fn new(updates_per_second: u32) -> Runner {
+        assert!(updates_per_second > 0);
+        Runner {
+            timestep: FixedTimestep::new(Duration::from_secs(1) / updates_per_second),
+            fps: None,
+            event_mask: KEY | MOUSE,
+            report: FrameReport::new(),
+        }
+    }
+
+    /// Limits the frame rate with `system::set_fps` when the runner starts. 0 means unlimited.
+    /// Without this, the current limit is kept.
+    pub fn fps(&mut self, fps: i32) -> &mut Runner {
+        assert!(fps >= 0);
+        self.fps = Some(fps);
+        self
+    }
+
+    /// Sets which events are passed to `App::handle_event`. The default is `KEY | MOUSE`.
+    pub fn event_mask(&mut self, event_mask: EventFlags) -> &mut Runner {
+        self.event_mask = event_mask;
+        self
+    }
+
+    /// Sets the most updates run in one frame. See `FixedTimestep::set_max_steps`.
+    pub fn max_updates_per_frame(&mut self, max_updates: u32) -> &mut Runner {
+        self.timestep.set_max_steps(max_updates);
+        self
+    }
+
+    /// Returns the timing of the frames run so far.
+    pub fn report(&self) -> &FrameReport {
+        &self.report
+    }
+
+    /// Returns how far the time is into the next update. See `FixedTimestep::alpha`.
+    pub fn alpha(&self) -> f32 {
+        self.timestep.alpha()
+    }
+
+    pub fn run<A: App>(&mut self, root: &mut Root, app: &mut A) {
+        if let Some(fps) = self.fps {
+            system::set_fps(fps);
+        }
+        let mut previous = Instant::now();
+        while !root.window_closed() && !app.is_finished() {
+            while let Some((_, event)) = input::check_for_event(self.event_mask) {
+                app.handle_event(event);
+            }
+
+            let now = Instant::now();
+            let updates = self.timestep.advance(now - previous);
+            previous = now;
+            for _ in 0..updates {
+                app.update(self.timestep.step());
+            }
+
+            app.render(root, self.timestep.alpha());
+            root.flush();
+            let length = Duration::from_secs_f32(system::get_last_frame_length().max(0.0));
+            self.report.record(length, updates);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FixedTimestep, FrameReport};
+    use std::time::Duration;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn fixed_timestep() {
+        let mut timestep = FixedTimestep::new(ms(20));
+        assert_eq!(timestep.advance(ms(15)), 0);
+        assert_eq!(timestep.advance(ms(15)), 1);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+        assert_eq!(timestep.advance(ms(50)), 3);
+        // Too far behind: the rest is dropped
+        timestep.set_max_steps(2);
+        assert_eq!(timestep.advance(ms(1000)), 2);
+        assert_eq!(timestep.advance(ms(0)), 0);
+    }
+
+    #[test]
+    fn frame_report() {
+        let mut report = FrameReport::new();
+        assert_eq!(report.fps(), 0.0);
+        report.record(ms(10), 1);
+        report.record(ms(30), 0);
+        assert_eq!(report.frames(), 2);
+        assert_eq!(report.updates(), 1);
+        assert_eq!(report.average_frame(), ms(20));
+        assert_eq!(report.worst_frame(), ms(30));
+        assert_eq!(report.last_frame(), ms(30));
+        assert!((report.fps() - 50.0).abs() < 1e-3);
+        report.reset();
+        assert_eq!(report.frames(), 0);
+    }
+}
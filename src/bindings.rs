@@ -39,7 +39,11 @@ impl <T, U: AsNative<T> + ?Sized> AsNative<T> for Box<U> {
 }
 
 pub fn keycode_from_native(input: self::ffi::TCOD_keycode_t) -> Option<KeyCode> {
-    match input as u32 {
+    keycode_from_u32(input as u32)
+}
+
+pub fn keycode_from_u32(input: u32) -> Option<KeyCode> {
+    match input {
         x @ 0 ..= 66 => Some(unsafe { transmute(x) }),
         _ => None
     }
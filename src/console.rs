@@ -63,8 +63,8 @@ use std::path::Path;
 use bindings::ffi::{self, TCOD_bkgnd_flag_t, TCOD_renderer_t, TCOD_font_flags_t, TCOD_alignment_t};
 use bindings::{AsNative, FromNative, c_bool, CString};
 
-use colors::Color;
-use input::{Key, KeyPressFlags};
+use colors::{Color, lerp};
+use input::{self, Key, KeyPressFlags};
 
 /// A type representing secondary consoles
 ///
@@ -277,24 +277,19 @@ impl Root {
     /// This function will wait for a keypress event from the user, returning the [KeyState](../input/struct.KeyState.html)
     /// that represents the event. If `flush` is true, all pending keypresses are flushed from the
     /// keyboard buffer. If false, it returns the first element from it.
+    ///
+    /// The key is read from the current [InputSource](../input/trait.InputSource.html).
     pub fn wait_for_keypress(&mut self, flush: bool) -> Key {
-        let tcod_key = unsafe {
-            ffi::TCOD_console_wait_for_keypress(flush as c_bool)
-        };
-        tcod_key.into()
+        input::with_input_source(|source| source.wait_for_keypress(flush))
     }
 
     /// This function checks if the user pressed a key. It returns the
     /// [KeyState](../input/struct.KeyState.html) representing the
     /// event if they have, or `None` if they have not.
+    ///
+    /// The key is read from the current [InputSource](../input/trait.InputSource.html).
     pub fn check_for_keypress(&self, status: KeyPressFlags) -> Option<Key> {
-        let tcod_key = unsafe {
-            ffi::TCOD_console_check_for_keypress(status.bits() as i32)
-        };
-        if tcod_key.vk == ffi::TCOD_keycode_t::TCODK_NONE {
-            return None;
-        }
-        Some(tcod_key.into())
+        input::with_input_source(|source| source.check_for_keypress(status))
     }
 
     /// Returns with true if the `Root` console has been closed.
@@ -623,6 +618,238 @@ impl<T> TcodString for T where T: AsRef<[u8]> + AsciiLiteral {
     }
 }
 
+const COLCTRL_FORE_RGB: u8 = ffi::TCOD_colctrl_t::TCOD_COLCTRL_FORE_RGB as u8;
+const COLCTRL_BACK_RGB: u8 = ffi::TCOD_colctrl_t::TCOD_COLCTRL_BACK_RGB as u8;
+const COLCTRL_STOP: u8 = ffi::TCOD_colctrl_t::TCOD_COLCTRL_STOP as u8;
+
+/// A string made of spans that each have their own foreground and background color
+///
+/// `StyledText` can be passed to all the `Console` printing methods (`print`, `print_rect`,
+/// `print_ex`, `print_rect_ex`, `get_height_rect`) as well as the `tcod_print!` macro. The
+/// colors are embedded into the string using libtcod's color control codes, so wrapping and
+/// alignment treat the whole text as a single string.
+///
+/// A span without a foreground or background color uses the console's default one. Background
+/// colors are only visible when the text is printed with a
+/// [BackgroundFlag](./enum.BackgroundFlag.html) other than `BackgroundFlag::None`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tcod::colors;
+/// use tcod::console::{Console, Offscreen, StyledText};
+///
+/// let mut con = Offscreen::new(40, 10);
+/// let message = StyledText::new()
+///     .text("The orc hits you for ")
+///     .fg("12", colors::RED)
+///     .text(" damage.");
+/// con.print_rect(0, 0, 20, 5, &message);
+///
+/// let same = StyledText::from_spans(vec![
+///     ("The orc hits you for ", None, None),
+///     ("12", Some(colors::RED), None),
+///     (" damage.", None, None),
+/// ]);
+/// assert_eq!(message, same);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyledText {
+    // Used by the UTF-8 printing functions: color components are stored as
+    // the `char`s with the same code point.
+    text: String,
+    // Used by the ASCII printing functions: color components are stored as
+    // raw bytes. Only valid when `ascii` is true.
+    bytes: Vec<u8>,
+    ascii: bool,
+}
+
+impl StyledText {
+    /// Creates an empty `StyledText`.
+    pub fn new() -> StyledText {
+        StyledText { text: String::new(), bytes: Vec::new(), ascii: true }
+    }
+
+    /// Creates a `StyledText` from `(text, foreground, background)` spans.
+    pub fn from_spans<I, T>(spans: I) -> StyledText
+        where I: IntoIterator<Item=(T, Option<Color>, Option<Color>)>, T: AsRef<str>
+    {
+        let mut result = StyledText::new();
+        for (text, foreground, background) in spans {
+            result.push(text, foreground, background);
+        }
+        result
+    }
+
+    /// Appends `text` printed in the console's default colors.
+    pub fn text<T>(mut self, text: T) -> StyledText where T: AsRef<str> {
+        self.push(text, None, None);
+        self
+    }
+
+    /// Appends `text` printed with the given foreground color.
+    pub fn fg<T>(mut self, text: T, foreground: Color) -> StyledText where T: AsRef<str> {
+        self.push(text, Some(foreground), None);
+        self
+    }
+
+    /// Appends `text` printed with the given background color.
+    pub fn bg<T>(mut self, text: T, background: Color) -> StyledText where T: AsRef<str> {
+        self.push(text, None, Some(background));
+        self
+    }
+
+    /// Appends `text` printed with the given foreground and background colors.
+    pub fn colored<T>(mut self, text: T, foreground: Color, background: Color) -> StyledText
+        where T: AsRef<str>
+    {
+        self.push(text, Some(foreground), Some(background));
+        self
+    }
+
+    /// Appends a span with optional foreground and background colors.
+    pub fn push<T>(&mut self, text: T, foreground: Option<Color>, background: Option<Color>)
+        where T: AsRef<str>
+    {
+        let text = text.as_ref();
+        if let Some(color) = foreground {
+            self.push_color(COLCTRL_FORE_RGB, color);
+        }
+        if let Some(color) = background {
+            self.push_color(COLCTRL_BACK_RGB, color);
+        }
+        self.text.push_str(text);
+        self.bytes.extend_from_slice(text.as_bytes());
+        self.ascii = self.ascii && text.is_ascii();
+        if foreground.is_some() || background.is_some() {
+            self.push_code(COLCTRL_STOP);
+        }
+    }
+
+    fn push_code(&mut self, code: u8) {
+        self.text.push(code as char);
+        self.bytes.push(code);
+    }
+
+    fn push_color(&mut self, code: u8, color: Color) {
+        self.push_code(code);
+        for &component in &[color.r, color.g, color.b] {
+            // A zero would terminate the C string early. The difference
+            // between 0 and 1 is not visible so we use that instead.
+            self.push_code(component.max(1));
+        }
+    }
+}
+
+impl AsRef<[u8]> for StyledText {
+    fn as_ref(&self) -> &[u8] {
+        self.text.as_bytes()
+    }
+}
+
+impl TcodString for StyledText {
+    fn as_ascii(&self) -> Option<&[u8]> {
+        if self.ascii {
+            Some(&self.bytes)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> TcodString for &'a StyledText {
+    fn as_ascii(&self) -> Option<&[u8]> {
+        (*self).as_ascii()
+    }
+}
+
+/// Support code for the `tcod_print!` macro, not meant to be used directly
+#[doc(hidden)]
+pub mod macro_support {
+    use colors::Color;
+    use super::{BackgroundFlag, Console, TcodString, TextAlignment};
+    use super::{COLCTRL_BACK_RGB, COLCTRL_FORE_RGB, COLCTRL_STOP};
+
+    /// The options of a `tcod_print!` call, evaluated before the console is borrowed
+    pub struct PrintOptions {
+        pub x: i32,
+        pub y: i32,
+        pub alignment: Option<TextAlignment>,
+        pub background_flag: Option<BackgroundFlag>,
+        pub rect: Option<(i32, i32)>,
+        pub foreground: Option<Color>,
+        pub background: Option<Color>,
+    }
+
+    /// Lets the macro reach the console with a method call, which borrows it through autoref
+    /// whether it's a console or a `&mut` to one.
+    pub trait PrintWithOptions {
+        fn print_with_options<T>(&mut self, options: PrintOptions, text: T)
+            where T: AsRef<[u8]> + TcodString;
+    }
+
+    impl<C: Console> PrintWithOptions for C {
+        fn print_with_options<T>(&mut self, options: PrintOptions, text: T)
+            where T: AsRef<[u8]> + TcodString
+        {
+            let alignment = options.alignment.unwrap_or_else(|| self.get_alignment());
+            // A background color isn't visible without a flag, so it implies `Set`
+            let background_flag = match (options.background_flag, options.background) {
+                (Some(flag), _) => flag,
+                (None, Some(_)) => BackgroundFlag::Set,
+                (None, None) => self.get_background_flag(),
+            };
+            let old_foreground = self.get_default_foreground();
+            let old_background = self.get_default_background();
+            if let Some(color) = options.foreground {
+                self.set_default_foreground(color);
+            }
+            if let Some(color) = options.background {
+                self.set_default_background(color);
+            }
+            match options.rect {
+                Some((width, height)) => self.print_rect_ex(options.x, options.y, width, height,
+                                                            background_flag, alignment, text),
+                None => self.print_ex(options.x, options.y, background_flag, alignment, text),
+            };
+            if options.foreground.is_some() {
+                self.set_default_foreground(old_foreground);
+            }
+            if options.background.is_some() {
+                self.set_default_background(old_background);
+            }
+        }
+    }
+
+    /// Cuts off every line of `text` after `width` characters
+    ///
+    /// The color control codes embedded by `StyledText` are kept and don't count towards the
+    /// width. This is what the `Clip` option uses.
+    pub fn clip_text<T>(text: T, width: i32) -> String where T: AsRef<[u8]> {
+        assert!(width >= 0);
+        let text = String::from_utf8_lossy(text.as_ref());
+        let mut result = String::with_capacity(text.len());
+        let mut line_length = 0;
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\n' {
+                line_length = 0;
+                result.push(c);
+            } else if c == COLCTRL_FORE_RGB as char || c == COLCTRL_BACK_RGB as char {
+                // The code is followed by the three color components
+                result.push(c);
+                result.extend(chars.by_ref().take(3));
+            } else if c >= '\u{1}' && c <= COLCTRL_STOP as char {
+                result.push(c);
+            } else if line_length < width {
+                line_length += 1;
+                result.push(c);
+            }
+        }
+        result
+    }
+}
+
 #[inline]
 fn to_wstring(text: &[u8]) -> Vec<char> {
     let mut ret = str::from_utf8(text).unwrap().chars().collect::<Vec<_>>();
@@ -630,6 +857,43 @@ fn to_wstring(text: &[u8]) -> Vec<char> {
     ret
 }
 
+/// The full contents of a single console cell: its glyph and both colors
+///
+/// Used with [get_cell](./trait.Console.html#method.get_cell),
+/// [set_cell](./trait.Console.html#method.set_cell) and the bulk
+/// [get_cells](./trait.Console.html#method.get_cells) and
+/// [set_cells](./trait.Console.html#method.set_cells) methods.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl Cell {
+    pub fn new(glyph: char, foreground: Color, background: Color) -> Cell {
+        Cell { glyph, foreground, background }
+    }
+}
+
+/// Returns the height of the `width`-wide rectangle that `len` cells (laid out
+/// row by row) fill up, checking that it fits on a `console_width` x
+/// `console_height` console when placed at `x, y`.
+fn bulk_rect_height(x: i32, y: i32, width: i32, len: usize,
+                    console_width: i32, console_height: i32) -> i32 {
+    assert!(x >= 0 && y >= 0 && width > 0);
+    assert_eq!(len % width as usize, 0, "slice length must be a multiple of the width");
+    let height = (len / width as usize) as i32;
+    assert!(x + width <= console_width && y + height <= console_height);
+    height
+}
+
+#[inline]
+fn native_char_to_glyph(ffi_char: i32) -> char {
+    assert!((0..256).contains(&ffi_char));
+    ffi_char as u8 as char
+}
+
 /// Defines the common functionality between `Root` and `Offscreen` consoles
 ///
 /// # Examples
@@ -765,8 +1029,7 @@ pub trait Console : AsNative<ffi::TCOD_console_t> {
         let ffi_char = unsafe {
             ffi::TCOD_console_get_char(*self.as_native(), x, y)
         };
-        assert!(ffi_char >= 0 && ffi_char < 256);
-        ffi_char as u8 as char
+        native_char_to_glyph(ffi_char)
     }
 
     /// Modifies the ASCII value of the cell located at `x, y`.
@@ -831,6 +1094,168 @@ pub trait Console : AsNative<ffi::TCOD_console_t> {
         }
     }
 
+    /// Returns the glyph and colors of the cell located at `x, y`.
+    fn get_cell(&self, x: i32, y: i32) -> Cell {
+        Cell {
+            glyph: self.get_char(x, y),
+            foreground: self.get_char_foreground(x, y),
+            background: self.get_char_background(x, y),
+        }
+    }
+
+    /// Sets the glyph and both colors of the cell located at `x, y` in a
+    /// single call. The background color is always set, ignoring the
+    /// console's background flag.
+    fn set_cell(&mut self, x: i32, y: i32, cell: Cell) {
+        self.put_char_ex(x, y, cell.glyph, cell.foreground, cell.background);
+    }
+
+    /// Sets the glyphs of a `width`-wide rectangle whose top left corner is
+    /// at `x, y`. The `chars` are laid out row by row, so their count must be
+    /// a multiple of `width` and the whole rectangle must fit on the console.
+    ///
+    /// This doesn't make drawing faster: libtcod is still called once per
+    /// cell, like `set_char` would be. To cut down the calls, use
+    /// `set_cells`. To set a single row, pass `chars.len()` as the width.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tcod::console::{Console, Offscreen};
+    ///
+    /// let mut con = Offscreen::new(80, 50);
+    /// let row: Vec<char> = "#....#".chars().collect();
+    /// con.set_chars(10, 5, row.len() as i32, &row);
+    /// ```
+    fn set_chars(&mut self, x: i32, y: i32, width: i32, chars: &[char]) {
+        bulk_rect_height(x, y, width, chars.len(), self.width(), self.height());
+        let con = unsafe { *self.as_native() };
+        for (i, &c) in chars.iter().enumerate() {
+            let (cx, cy) = (x + i as i32 % width, y + i as i32 / width);
+            unsafe {
+                ffi::TCOD_console_set_char(con, cx, cy, c as i32);
+            }
+        }
+    }
+
+    /// Sets the foreground colors of a `width`-wide rectangle whose top left
+    /// corner is at `x, y`. See `set_chars` for how the slice is laid out.
+    fn set_foregrounds(&mut self, x: i32, y: i32, width: i32, colors: &[Color]) {
+        bulk_rect_height(x, y, width, colors.len(), self.width(), self.height());
+        let con = unsafe { *self.as_native() };
+        for (i, color) in colors.iter().enumerate() {
+            let (cx, cy) = (x + i as i32 % width, y + i as i32 / width);
+            unsafe {
+                ffi::TCOD_console_set_char_foreground(con, cx, cy, *color.as_native());
+            }
+        }
+    }
+
+    /// Sets the background colors of a `width`-wide rectangle whose top left
+    /// corner is at `x, y`, blending them in according to `background_flag`.
+    /// See `set_chars` for how the slice is laid out.
+    fn set_backgrounds(&mut self, x: i32, y: i32, width: i32, colors: &[Color],
+                       background_flag: BackgroundFlag) {
+        bulk_rect_height(x, y, width, colors.len(), self.width(), self.height());
+        let con = unsafe { *self.as_native() };
+        for (i, color) in colors.iter().enumerate() {
+            let (cx, cy) = (x + i as i32 % width, y + i as i32 / width);
+            unsafe {
+                ffi::TCOD_console_set_char_background(con, cx, cy, *color.as_native(),
+                                                      background_flag.into());
+            }
+        }
+    }
+
+    /// Sets the glyphs and colors of a `width`-wide rectangle whose top left
+    /// corner is at `x, y`. See `set_chars` for how the slice is laid out and
+    /// `set_cell` for how each cell is updated.
+    ///
+    /// Each cell takes one libtcod call instead of the three `set_char`,
+    /// `set_char_foreground` and `set_char_background` need, so this is the
+    /// method to use when redrawing a whole map.
+    fn set_cells(&mut self, x: i32, y: i32, width: i32, cells: &[Cell]) {
+        bulk_rect_height(x, y, width, cells.len(), self.width(), self.height());
+        let con = unsafe { *self.as_native() };
+        for (i, cell) in cells.iter().enumerate() {
+            let (cx, cy) = (x + i as i32 % width, y + i as i32 / width);
+            unsafe {
+                ffi::TCOD_console_put_char_ex(con, cx, cy, cell.glyph as i32,
+                                              *cell.foreground.as_native(),
+                                              *cell.background.as_native());
+            }
+        }
+    }
+
+    /// Returns the glyphs of the given rectangle, row by row.
+    fn get_chars(&self, x: i32, y: i32, width: i32, height: i32) -> Vec<char> {
+        assert!(height >= 0);
+        bulk_rect_height(x, y, width, (width * height) as usize, self.width(), self.height());
+        let con = unsafe { *self.as_native() };
+        let mut result = Vec::with_capacity((width * height) as usize);
+        for cy in y..y + height {
+            for cx in x..x + width {
+                let ffi_char = unsafe { ffi::TCOD_console_get_char(con, cx, cy) };
+                result.push(native_char_to_glyph(ffi_char));
+            }
+        }
+        result
+    }
+
+    /// Returns the foreground colors of the given rectangle, row by row.
+    fn get_foregrounds(&self, x: i32, y: i32, width: i32, height: i32) -> Vec<Color> {
+        assert!(height >= 0);
+        bulk_rect_height(x, y, width, (width * height) as usize, self.width(), self.height());
+        let con = unsafe { *self.as_native() };
+        let mut result = Vec::with_capacity((width * height) as usize);
+        for cy in y..y + height {
+            for cx in x..x + width {
+                result.push(unsafe {
+                    FromNative::from_native(ffi::TCOD_console_get_char_foreground(con, cx, cy))
+                });
+            }
+        }
+        result
+    }
+
+    /// Returns the background colors of the given rectangle, row by row.
+    fn get_backgrounds(&self, x: i32, y: i32, width: i32, height: i32) -> Vec<Color> {
+        assert!(height >= 0);
+        bulk_rect_height(x, y, width, (width * height) as usize, self.width(), self.height());
+        let con = unsafe { *self.as_native() };
+        let mut result = Vec::with_capacity((width * height) as usize);
+        for cy in y..y + height {
+            for cx in x..x + width {
+                result.push(unsafe {
+                    FromNative::from_native(ffi::TCOD_console_get_char_background(con, cx, cy))
+                });
+            }
+        }
+        result
+    }
+
+    /// Returns the glyphs and colors of the given rectangle, row by row.
+    fn get_cells(&self, x: i32, y: i32, width: i32, height: i32) -> Vec<Cell> {
+        assert!(height >= 0);
+        bulk_rect_height(x, y, width, (width * height) as usize, self.width(), self.height());
+        let con = unsafe { *self.as_native() };
+        let mut result = Vec::with_capacity((width * height) as usize);
+        for cy in y..y + height {
+            for cx in x..x + width {
+                unsafe {
+                    result.push(Cell {
+                        glyph: native_char_to_glyph(ffi::TCOD_console_get_char(con, cx, cy)),
+                        foreground: FromNative::from_native(
+                            ffi::TCOD_console_get_char_foreground(con, cx, cy)),
+                        background: FromNative::from_native(
+                            ffi::TCOD_console_get_char_background(con, cx, cy)),
+                    });
+                }
+            }
+        }
+        result
+    }
+
     /// Clears the console with its default background color
     fn clear(&mut self) {
         unsafe {
@@ -1094,6 +1519,225 @@ pub fn blit<T, U>(source_console: &T,
     }
 }
 
+/// Clockwise rotation applied by [blit_transformed](./fn.blit_transformed.html)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Rotation {
+    None,
+    Clockwise90,
+    Clockwise180,
+    Clockwise270,
+}
+
+/// The transformations applied by [blit_transformed](./fn.blit_transformed.html)
+///
+/// The blitted region is flipped first and rotated afterwards. Only the positions of the cells
+/// change, the glyphs themselves are not flipped or rotated.
+///
+/// Like `RootInitializer`, it uses method chaining to set the options:
+///
+/// * `flip_horizontal`, `flip_vertical`: mirror the region
+/// * `rotate`: rotate the region in 90 degree steps, see [Rotation](./enum.Rotation.html)
+/// * `key_color`: skip the source cells with this background color (the equivalent of the
+///   source console's `set_key_color` for `blit`)
+/// * `remap_colors`: pass every foreground and background color through a closure
+/// * `remap_table`: replace the colors found in a `(from, to)` lookup table. The colors not in
+///   the table are kept
+/// * `alpha`: the foreground and background alpha used to blend the cells, like the alpha
+///   arguments of `blit`. Both are 1.0 (no blending) by default
+pub struct BlitTransform<'a> {
+    flip_horizontal: bool,
+    flip_vertical: bool,
+    rotation: Rotation,
+    key_color: Option<Color>,
+    remap: Option<Box<dyn FnMut(Color) -> Color + 'a>>,
+    foreground_alpha: f32,
+    background_alpha: f32,
+}
+
+impl<'a> Default for BlitTransform<'a> {
+    fn default() -> BlitTransform<'a> {
+        BlitTransform::new()
+    }
+}
+
+impl<'a> BlitTransform<'a> {
+    pub fn new() -> BlitTransform<'a> {
+        BlitTransform {
+            flip_horizontal: false,
+            flip_vertical: false,
+            rotation: Rotation::None,
+            key_color: None,
+            remap: None,
+            foreground_alpha: 1.0,
+            background_alpha: 1.0,
+        }
+    }
+
+    pub fn flip_horizontal(&mut self, flip: bool) -> &mut BlitTransform<'a> {
+        self.flip_horizontal = flip;
+        self
+    }
+
+    pub fn flip_vertical(&mut self, flip: bool) -> &mut BlitTransform<'a> {
+        self.flip_vertical = flip;
+        self
+    }
+
+    pub fn rotate(&mut self, rotation: Rotation) -> &mut BlitTransform<'a> {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn key_color(&mut self, color: Color) -> &mut BlitTransform<'a> {
+        self.key_color = Some(color);
+        self
+    }
+
+    pub fn remap_colors<F>(&mut self, remap: F) -> &mut BlitTransform<'a>
+        where F: FnMut(Color) -> Color + 'a
+    {
+        self.remap = Some(Box::new(remap));
+        self
+    }
+
+    pub fn remap_table(&mut self, table: &'a [(Color, Color)]) -> &mut BlitTransform<'a> {
+        self.remap_colors(move |color| {
+            table.iter()
+                .find(|&&(from, _)| from == color)
+                .map_or(color, |&(_, to)| to)
+        })
+    }
+
+    pub fn alpha(&mut self, foreground_alpha: f32,
+                 background_alpha: f32) -> &mut BlitTransform<'a> {
+        self.foreground_alpha = foreground_alpha;
+        self.background_alpha = background_alpha;
+        self
+    }
+
+    /// Applies the key color and the color remapping to a source cell. Returns `None` for the
+    /// cells that are skipped.
+    fn transform_cell(&mut self, cell: Cell) -> Option<Cell> {
+        if self.key_color == Some(cell.background) {
+            return None;
+        }
+        Some(match self.remap {
+            Some(ref mut remap) => Cell {
+                glyph: cell.glyph,
+                foreground: remap(cell.foreground),
+                background: remap(cell.background),
+            },
+            None => cell,
+        })
+    }
+
+    /// Maps the position of a cell inside a `width` x `height` region to its
+    /// position inside the transformed region.
+    fn transform_position(&self, (x, y): (i32, i32), width: i32, height: i32) -> (i32, i32) {
+        let x = if self.flip_horizontal { width - 1 - x } else { x };
+        let y = if self.flip_vertical { height - 1 - y } else { y };
+        match self.rotation {
+            Rotation::None => (x, y),
+            Rotation::Clockwise90 => (height - 1 - y, x),
+            Rotation::Clockwise180 => (width - 1 - x, height - 1 - y),
+            Rotation::Clockwise270 => (y, width - 1 - x),
+        }
+    }
+}
+
+/// Blends a source cell onto a destination cell the same way libtcod's blit does.
+fn blend_cell(source: Cell, destination: Cell,
+              foreground_alpha: f32, background_alpha: f32) -> Cell {
+    if foreground_alpha == 1.0 && background_alpha == 1.0 {
+        return source;
+    }
+    let mut result = destination;
+    result.background = lerp(destination.background, source.background, background_alpha);
+    if source.glyph == ' ' {
+        result.foreground = lerp(destination.foreground, source.background, background_alpha);
+    } else if destination.glyph == ' ' {
+        result.glyph = source.glyph;
+        result.foreground = lerp(result.background, source.foreground, foreground_alpha);
+    } else if destination.glyph == source.glyph {
+        result.foreground = lerp(destination.foreground, source.foreground, foreground_alpha);
+    } else if foreground_alpha < 0.5 {
+        result.foreground = lerp(destination.foreground, result.background, foreground_alpha * 2.0);
+    } else {
+        result.glyph = source.glyph;
+        result.foreground = lerp(result.background, source.foreground,
+                                 (foreground_alpha - 0.5) * 2.0);
+    }
+    result
+}
+
+/// Blits the contents of one console onto an other, flipping, rotating or recoloring them
+///
+/// This works like [blit](./fn.blit.html), but the region taken from the source console goes
+/// through the given [BlitTransform](./struct.BlitTransform.html) first. After a 90 or 270
+/// degree rotation, the region takes `source_height` columns and `source_width` rows on the
+/// destination console. The parts that don't fit on the destination console are skipped.
+/// The foreground and background alpha are set with `BlitTransform::alpha`.
+///
+/// # Examples
+///
+/// Drawing a mirrored copy of a sprite, flashing red and half transparent:
+///
+/// ```no_run
+/// use tcod::colors::{self, Color};
+/// use tcod::console::{self, BlitTransform, Offscreen, Rotation};
+///
+/// let sprite = Offscreen::new(5, 3);
+/// let mut map = Offscreen::new(80, 50);
+///
+/// let mut transform = BlitTransform::new();
+/// transform.flip_horizontal(true)
+///     .rotate(Rotation::Clockwise90)
+///     .remap_colors(|color| colors::lerp(color, colors::RED, 0.5))
+///     .alpha(0.5, 0.5);
+/// console::blit_transformed(&sprite, (0, 0), (0, 0), &mut map, (10, 10), &mut transform);
+/// ```
+pub fn blit_transformed<T, U>(source_console: &T,
+                              (source_x, source_y): (i32, i32),
+                              (source_width, source_height): (i32, i32),
+                              destination_console: &mut U,
+                              (destination_x, destination_y): (i32, i32),
+                              transform: &mut BlitTransform)
+    where T: Console,
+          U: Console {
+    assert!(source_x >= 0 && source_y >= 0 &&
+            source_width >= 0 && source_height >= 0); // If width or height is 0, the source width/height is used.
+    let width = if source_width == 0 { source_console.width() - source_x } else { source_width };
+    let height = if source_height == 0 { source_console.height() - source_y } else { source_height };
+    if width <= 0 || height <= 0 {
+        return;
+    }
+
+    let cells = source_console.get_cells(source_x, source_y, width, height);
+    let (destination_width, destination_height) =
+        (destination_console.width(), destination_console.height());
+    let (foreground_alpha, background_alpha) = (transform.foreground_alpha,
+                                                transform.background_alpha);
+    for (i, &cell) in cells.iter().enumerate() {
+        let (x, y) = transform.transform_position((i as i32 % width, i as i32 / width),
+                                                  width, height);
+        let (x, y) = (destination_x + x, destination_y + y);
+        if x < 0 || y < 0 || x >= destination_width || y >= destination_height {
+            continue;
+        }
+        let cell = match transform.transform_cell(cell) {
+            Some(cell) => cell,
+            None => continue,
+        };
+        let cell = if foreground_alpha == 1.0 && background_alpha == 1.0 {
+            cell
+        } else {
+            blend_cell(cell, destination_console.get_cell(x, y),
+                       foreground_alpha, background_alpha)
+        };
+        destination_console.set_cell(x, y, cell);
+    }
+}
+
 impl<'a, T: Console + ?Sized> Console for &'a T {}
 
 impl<T: Console + ?Sized> Console for Box<T> {}
@@ -1187,8 +1831,13 @@ native_enum_convert!(FontType, TCOD_font_flags_t);
 
 #[cfg(test)]
 mod test {
+    use std::env;
     use std::path::Path;
-    use super::Root;
+    use super::{Root, StyledText, TcodString, BlitTransform, Rotation, bulk_rect_height};
+    use super::{Cell, Console, Offscreen, blend_cell};
+    use super::macro_support::clip_text;
+    use bindings::ffi;
+    use colors::Color;
     use super::FontLayout::AsciiInCol;
 
     #[test]
@@ -1213,4 +1862,158 @@ mod test {
         let s: &str = &string;
         Root::initializer().font(s, AsciiInCol);
     }
+
+    #[test]
+    fn test_bulk_rect_height() {
+        assert_eq!(bulk_rect_height(0, 0, 10, 30, 80, 50), 3);
+        assert_eq!(bulk_rect_height(70, 49, 10, 10, 80, 50), 1);
+        assert_eq!(bulk_rect_height(5, 5, 4, 0, 80, 50), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bulk_rect_height_ragged() {
+        bulk_rect_height(0, 0, 10, 25, 80, 50);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bulk_rect_height_out_of_bounds() {
+        bulk_rect_height(75, 0, 10, 10, 80, 50);
+    }
+
+    #[test]
+    fn test_styled_text_encoding() {
+        let text = StyledText::new()
+            .text("a")
+            .fg("b", Color::new(200, 0, 3))
+            .bg("c", Color::new(4, 5, 6));
+        let expected = [b'a',
+                        6, 200, 1, 3, b'b', 8,
+                        7, 4, 5, 6, b'c', 8];
+        assert_eq!(text.as_ascii(), Some(&expected[..]));
+        let chars: Vec<u32> = ::std::str::from_utf8(text.as_ref()).unwrap()
+            .chars().map(|c| c as u32).collect();
+        let expected: Vec<u32> = expected.iter().map(|&b| b as u32).collect();
+        assert_eq!(chars, expected);
+    }
+
+    #[test]
+    fn test_styled_text_unicode() {
+        let text = StyledText::from_spans(vec![("héllo", Some(Color::new(1, 2, 3)), None)]);
+        assert_eq!(text.as_ascii(), None);
+        assert_eq!(::std::str::from_utf8(text.as_ref()).unwrap(), "\u{6}\u{1}\u{2}\u{3}héllo\u{8}");
+    }
+
+    #[test]
+    fn test_clip_text() {
+        assert_eq!(clip_text("hello world", 5), "hello");
+        assert_eq!(clip_text("ab\nabcdef", 3), "ab\nabc");
+        let styled = StyledText::new().fg("abcdef", Color::new(200, 0, 3)).text("gh");
+        let clipped = clip_text(&styled, 4);
+        assert_eq!(clipped, "\u{6}\u{c8}\u{1}\u{3}abcd\u{8}");
+    }
+
+    #[test]
+    fn test_cells_round_trip() {
+        // libtcod ignores the glyphs until SDL's startup has sized the font tables. The dummy
+        // video driver lets it start without a window.
+        env::set_var("SDL_VIDEODRIVER", "dummy");
+        unsafe {
+            ffi::TCOD_sys_startup();
+        }
+        let mut con = Offscreen::new(4, 3);
+        let cells = [Cell::new('@', Color::new(255, 0, 0), Color::new(0, 0, 255)),
+                     Cell::new('#', Color::new(1, 2, 3), Color::new(4, 5, 6)),
+                     Cell::new('.', Color::new(0, 255, 0), Color::new(0, 0, 0)),
+                     Cell::new('x', Color::new(10, 20, 30), Color::new(40, 50, 60))];
+        con.set_cells(1, 1, 2, &cells);
+        assert_eq!(con.get_cells(1, 1, 2, 2), cells);
+        assert_eq!(con.get_cell(2, 2), cells[3]);
+        assert_eq!(con.get_chars(1, 1, 2, 2), ['@', '#', '.', 'x']);
+    }
+
+    #[test]
+    fn test_blend_cell() {
+        let source = Cell::new('@', Color::new(200, 0, 0), Color::new(0, 0, 200));
+        let destination = Cell::new('#', Color::new(0, 200, 0), Color::new(0, 0, 0));
+        assert_eq!(blend_cell(source, destination, 1.0, 1.0), source);
+        assert_eq!(blend_cell(source, destination, 0.0, 0.0), destination);
+
+        // Different glyphs: the destination one fades out below 0.5, the source one fades in
+        // above
+        assert_eq!(blend_cell(source, destination, 0.25, 0.5),
+                   Cell::new('#', Color::new(0, 100, 50), Color::new(0, 0, 100)));
+        assert_eq!(blend_cell(source, destination, 0.75, 0.5),
+                   Cell::new('@', Color::new(100, 0, 50), Color::new(0, 0, 100)));
+
+        let same_glyph = Cell { glyph: '@', ..destination };
+        assert_eq!(blend_cell(source, same_glyph, 0.5, 0.5),
+                   Cell::new('@', Color::new(100, 100, 0), Color::new(0, 0, 100)));
+        let space = Cell { glyph: ' ', ..source };
+        assert_eq!(blend_cell(space, destination, 0.5, 0.5),
+                   Cell::new('#', Color::new(0, 100, 100), Color::new(0, 0, 100)));
+        let empty = Cell { glyph: ' ', ..destination };
+        assert_eq!(blend_cell(source, empty, 0.5, 0.5),
+                   Cell::new('@', Color::new(100, 0, 50), Color::new(0, 0, 100)));
+    }
+
+    #[test]
+    fn test_blit_transform_cell() {
+        let (red, blue, green) = (Color::new(255, 0, 0), Color::new(0, 0, 255),
+                                  Color::new(0, 255, 0));
+        let table = [(red, green)];
+        let mut transform = BlitTransform::new();
+        transform.key_color(blue).remap_table(&table);
+        assert_eq!(transform.transform_cell(Cell::new('@', red, blue)), None);
+        assert_eq!(transform.transform_cell(Cell::new('@', red, red)),
+                   Some(Cell::new('@', green, green)));
+        assert_eq!(transform.transform_cell(Cell::new('#', blue, Color::new(1, 2, 3))),
+                   Some(Cell::new('#', blue, Color::new(1, 2, 3))));
+    }
+
+    #[test]
+    fn test_blit_transform_position() {
+        // A 3x2 region:
+        // a b c
+        // d e f
+        let (width, height) = (3, 2);
+        let (a, f) = ((0, 0), (2, 1));
+
+        let mut transform = BlitTransform::new();
+        assert_eq!(transform.transform_position(a, width, height), (0, 0));
+
+        transform.flip_horizontal(true);
+        assert_eq!(transform.transform_position(a, width, height), (2, 0));
+        transform.flip_horizontal(false).flip_vertical(true);
+        assert_eq!(transform.transform_position(a, width, height), (0, 1));
+        transform.flip_vertical(false);
+
+        // d a
+        // e b
+        // f c
+        transform.rotate(Rotation::Clockwise90);
+        assert_eq!(transform.transform_position(a, width, height), (1, 0));
+        assert_eq!(transform.transform_position(f, width, height), (0, 2));
+
+        transform.rotate(Rotation::Clockwise180);
+        assert_eq!(transform.transform_position(a, width, height), (2, 1));
+
+        // c f
+        // b e
+        // a d
+        transform.rotate(Rotation::Clockwise270);
+        assert_eq!(transform.transform_position(a, width, height), (0, 2));
+        assert_eq!(transform.transform_position(f, width, height), (1, 0));
+    }
+
+    #[test]
+    fn test_blit_remap_table() {
+        let table = [(Color::new(1, 2, 3), Color::new(4, 5, 6))];
+        let mut transform = BlitTransform::new();
+        transform.remap_table(&table);
+        let remap = transform.remap.as_mut().unwrap();
+        assert_eq!(remap(Color::new(1, 2, 3)), Color::new(4, 5, 6));
+        assert_eq!(remap(Color::new(7, 8, 9)), Color::new(7, 8, 9));
+    }
 }
@@ -1,106 +1,136 @@
+/// Prints formatted text on a console
+///
+/// The first argument is the console and the second one is the position, given as `At(x, y)`.
+/// The position, the options and the text are evaluated before the console is borrowed, so they
+/// can read from the console too.
+/// They are followed by any number of these options, in any order:
+///
+/// * `Align(alignment)`: the [TextAlignment](console/enum.TextAlignment.html) to use instead of
+///   the console's default one
+/// * `Bg(flag)`: the [BackgroundFlag](console/enum.BackgroundFlag.html) to use instead of the
+///   console's default one
+/// * `Fg(color)`: the foreground color to print with. The console's default foreground color is
+///   restored afterwards
+/// * `BgColor(color)`: the background color to print with. The console's default background
+///   color is restored afterwards. Unless `Bg` is given too, `BackgroundFlag::Set` is used so
+///   the color is actually visible
+/// * `Wrap(width, height)`: wrap the text inside a `width` x `height` rectangle
+/// * `Clip(width, height)`: like `Wrap`, but the lines longer than `width` are cut off instead of
+///   wrapped. `Wrap` and `Clip` can't be used together
+///
+/// The rest of the arguments is either a format string with its arguments (as in `format!`) or a
+/// single expression such as a [StyledText](console/struct.StyledText.html), which is printed as
+/// is.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[macro_use] extern crate tcod;
+/// use tcod::colors;
+/// use tcod::console::{Console, Offscreen, TextAlignment};
+///
+/// fn draw_status(con: &mut Offscreen, hp: i32) {
+///     tcod_print!(con, At(1, 1), "HP: {}", hp);
+///     tcod_print!(con, At(con.width() - 1, 1), Align(TextAlignment::Right), "{}", con.width());
+/// }
+///
+/// # fn main() {
+/// let mut con = Offscreen::new(80, 50);
+/// let hp = 12;
+/// draw_status(&mut con, hp);
+/// tcod_print!(con, At(78, 2), Fg(colors::RED), Align(TextAlignment::Right), "HP: {}", hp);
+/// tcod_print!(con, At(1, 3), Clip(10, 1), BgColor(colors::DARK_BLUE), "Truncated to ten cells");
+/// # }
+/// ```
 #[macro_export]
 macro_rules! tcod_print {
-    // ABW
-    ($con: expr, At($x: expr, $y: expr), Align($alignment: expr),
-     Bg($bg: expr), Wrap($width: expr, $height: expr), $($arg: tt)*) => (
-        $con.print_rect_ex($x, $y, $width, $height, $bg, $alignment, format!($($arg)*));
+    // The text is either a format string with its arguments or a single
+    // expression such as a `StyledText` that is passed through as is.
+    (@text $fmt: literal) => (
+        format!($fmt)
     );
 
-    // AWB
-    ($con: expr, At($x: expr, $y: expr), Align($alignment: expr),
-     Wrap($width: expr, $height: expr), Bg($bg: expr), $($arg: tt)*) => (
-        $con.print_rect_ex($x, $y, $width, $height, $bg, $alignment, format!($($arg)*));
+    (@text $text: expr) => (
+        $text
     );
 
-    // BAW
-    ($con: expr, At($x: expr, $y: expr), Bg($bg: expr),
-     Align($alignment: expr), Wrap($width: expr, $height: expr), $($arg: tt)*) => (
-        $con.print_rect_ex($x, $y, $width, $height, $bg, $alignment, format!($($arg)*));
+    (@text $($arg: tt)*) => (
+        format!($($arg)*)
     );
 
-    // BWA
-    ($con: expr, At($x: expr, $y: expr), Bg($bg: expr),
-     Wrap($width: expr, $height: expr), Align($alignment: expr), $($arg: tt)*) => (
-        $con.print_rect_ex($x, $y, $width, $height, $bg, $alignment, format!($($arg)*));
+    // Option munching. Every option fills its own (initially empty) slot:
+    // [align] [bg] [rect] [clip] [fg] [bg color]
+    (@opts $con: expr, $pos: tt, [] $bg: tt $rect: tt $clip: tt $fg: tt $bgc: tt,
+     Align($alignment: expr), $($rest: tt)*) => (
+        tcod_print!(@opts $con, $pos, [$alignment] $bg $rect $clip $fg $bgc, $($rest)*)
     );
 
-    // WAB
-    ($con: expr, At($x: expr, $y: expr), Wrap($width: expr, $height: expr),
-     Align($alignment: expr), Bg($bg: expr), $($arg: tt)*) => (
-        $con.print_rect_ex($x, $y, $width, $height, $bg, $alignment, format!($($arg)*));
+    (@opts $con: expr, $pos: tt, $align: tt [] $rect: tt $clip: tt $fg: tt $bgc: tt,
+     Bg($bg: expr), $($rest: tt)*) => (
+        tcod_print!(@opts $con, $pos, $align [$bg] $rect $clip $fg $bgc, $($rest)*)
     );
 
-    // WBA
-    ($con: expr, At($x: expr, $y: expr), Wrap($width: expr, $height: expr),
-     Bg($bg: expr), Align($alignment: expr), $($arg: tt)*) => (
-        $con.print_rect_ex($x, $y, $width, $height, $bg, $alignment, format!($($arg)*));
+    (@opts $con: expr, $pos: tt, $align: tt $bg: tt [] [] $fg: tt $bgc: tt,
+     Wrap($width: expr, $height: expr), $($rest: tt)*) => (
+        tcod_print!(@opts $con, $pos, $align $bg [$width, $height] [] $fg $bgc, $($rest)*)
     );
 
-    // AB
-    ($con: expr, At($x: expr, $y: expr), Align($bg: expr), Bg($alignment: expr), $($arg: tt)*) => (
-        $con.print_ex($x, $y, $bg, $alignment, format!($($arg)*));
+    (@opts $con: expr, $pos: tt, $align: tt $bg: tt [] [] $fg: tt $bgc: tt,
+     Clip($width: expr, $height: expr), $($rest: tt)*) => (
+        tcod_print!(@opts $con, $pos, $align $bg [$width, $height] [$width] $fg $bgc, $($rest)*)
     );
 
-    // AW
-    ($con: expr, At($x: expr, $y: expr), Align($alignment: expr), Wrap($width: expr, $height: expr), $($arg: tt)*) => (
-        {
-            let bg = $con.get_background_flag();
-            $con.print_rect_ex($x, $y, $width, $height, bg, $alignment, format!($($arg)*));
-        }
+    (@opts $con: expr, $pos: tt, $align: tt $bg: tt $rect: tt $clip: tt [] $bgc: tt,
+     Fg($color: expr), $($rest: tt)*) => (
+        tcod_print!(@opts $con, $pos, $align $bg $rect $clip [$color] $bgc, $($rest)*)
     );
 
-    // BA
-    ($con: expr, At($x: expr, $y: expr), Bg($bg: expr), Align($alignment: expr), $($arg: tt)*) => (
-        $con.print_ex($x, $y, $bg, $alignment, format!($($arg)*));
+    (@opts $con: expr, $pos: tt, $align: tt $bg: tt $rect: tt $clip: tt $fg: tt [],
+     BgColor($color: expr), $($rest: tt)*) => (
+        tcod_print!(@opts $con, $pos, $align $bg $rect $clip $fg [$color], $($rest)*)
     );
 
-    // BW
-    ($con: expr, At($x: expr, $y: expr), Bg($bg: expr), Wrap($width: expr, $height: expr), $($arg: tt)*) => (
+    // No options left, everything else is the text. The console is only
+    // borrowed at the end, through autoref, so `&mut` bindings work as well.
+    (@opts $con: expr, ($x: expr, $y: expr), $align: tt $bg: tt $rect: tt $clip: tt $fg: tt $bgc: tt,
+     $($arg: tt)*) => (
         {
-            let alignment = $con.get_alignment();
-            $con.print_rect_ex($x, $y, $width, $height, $bg, alignment, format!($($arg)*));
+            use $crate::console::macro_support::PrintWithOptions;
+            let options = $crate::console::macro_support::PrintOptions {
+                x: $x,
+                y: $y,
+                alignment: tcod_print!(@option $align),
+                background_flag: tcod_print!(@option $bg),
+                rect: tcod_print!(@option $rect),
+                foreground: tcod_print!(@option $fg),
+                background: tcod_print!(@option $bgc),
+            };
+            let text = tcod_print!(@clip $clip, tcod_print!(@text $($arg)*));
+            $con.print_with_options(options, text)
         }
     );
 
-    // WA
-    ($con: expr, At($x: expr, $y: expr), Wrap($width: expr, $height: expr), Align($alignment: expr), $($arg: tt)*) => (
-        {
-            let bg = $con.get_background_flag();
-            $con.print_rect_ex($x, $y, $width, $height, bg, $alignment, format!($($arg)*));
-        }
+    (@option []) => (
+        None
     );
 
-    // WB
-    ($con: expr, At($x: expr, $y: expr), Wrap($width: expr, $height: expr), Bg($bg: expr), $($arg: tt)*) => (
-        {
-            let alignment = $con.get_alignment();
-            $con.print_rect_ex($x, $y, $width, $height, $bg, alignment, format!($($arg)*));
-        }
+    (@option [$width: expr, $height: expr]) => (
+        Some(($width, $height))
     );
 
-    // A
-    ($con: expr, At($x: expr, $y: expr), Align($alignment: expr), $($arg: tt)*) => (
-        {
-            let bg = $con.get_background_flag();
-            $con.print_ex($x, $y, bg, $alignment, format!($($arg)*));
-        }
+    (@option [$value: expr]) => (
+        Some($value)
     );
 
-    // B
-    ($con: expr, At($x: expr, $y: expr), Bg($bg: expr), $($arg: tt)*) => (
-        {
-            let alignment = $con.get_alignment();
-            $con.print_ex($x, $y, $bg, alignment, format!($($arg)*));
-        }
+    (@clip [], $text: expr) => (
+        $text
     );
 
-    // W
-    ($con: expr, At($x: expr, $y: expr), Wrap($width: expr, $height: expr), $($arg: tt)*) => (
-        $con.print_rect($x, $y, $width, $height, format!($($arg)*));
+    (@clip [$width: expr], $text: expr) => (
+        $crate::console::macro_support::clip_text($text, $width)
     );
 
-    // None
     ($con: expr, At($x: expr, $y: expr), $($arg: tt)*) => (
-        $con.print($x, $y, format!($($arg)*));
+        tcod_print!(@opts $con, ($x, $y), [] [] [] [] [] [], $($arg)*)
     );
 }
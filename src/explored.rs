@@ -0,0 +1,178 @@
+//! Remembering the explored cells (fog of war)
+//!
+//! An [ExploredMap](./struct.ExploredMap.html) accumulates every cell that has been in the field
+//! of view. `render_fog` then draws the remembered cells dimmed and hides the ones never seen:
+//!
+//! ```no_run
+//! use tcod::console::{Console, Offscreen};
+//! use tcod::explored::{ExploredMap, FogStyle};
+//! use tcod::map::{FovAlgorithm, Map};
+//!
+//! let mut map = Map::new(80, 50);
+//! let mut explored = ExploredMap::new(80, 50);
+//! let mut console = Offscreen::new(80, 50);
+//!
+//! map.compute_fov(10, 10, 8, true, FovAlgorithm::Basic);
+//! explored.update(&map);
+//! // ... draw the whole map onto the console ...
+//! explored.render_fog(&mut console, &map, &FogStyle::default());
+//! ```
+//!
+//! With the `serialization` feature, `ExploredMap` can be saved along with the rest of the game.
+
+use colors::{self, Color};
+use console::{BackgroundFlag, Console};
+use fov::Visibility;
+use map::Map;
+
+/// The cells seen so far
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct ExploredMap {
+    width: i32,
+    height: i32,
+    cells: Vec<bool>,
+}
+
+impl ExploredMap {
+    /// Creates a `width` x `height` map with nothing explored.
+    pub fn new(width: i32, height: i32) -> ExploredMap {
+        assert!(width > 0 && height > 0);
+        ExploredMap { width, height, cells: vec![false; (width * height) as usize] }
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        assert!(x >= 0 && y >= 0 && x < self.width && y < self.height);
+        (y * self.width + x) as usize
+    }
+
+    pub fn is_explored(&self, x: i32, y: i32) -> bool {
+        self.cells[self.index(x, y)]
+    }
+
+    /// Marks a cell as explored or not, e.g. for a magic map revealing the level.
+    pub fn set_explored(&mut self, x: i32, y: i32, explored: bool) {
+        let index = self.index(x, y);
+        self.cells[index] = explored;
+    }
+
+    /// Marks the cells in the field of view computed last as explored.
+    pub fn update(&mut self, map: &Map) {
+        assert!(map.size() == self.size(), "the map has a different size");
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if map.is_in_fov(x, y) {
+                    self.set_explored(x, y, true);
+                }
+            }
+        }
+    }
+
+    /// Marks the cells visible in the result of `fov::compute_symmetric` as explored.
+    pub fn update_from(&mut self, visibility: &Visibility) {
+        assert!((visibility.width(), visibility.height()) == self.size(),
+                "the visibility has a different size");
+        for (x, y) in visibility.visible_cells() {
+            self.set_explored(x, y, true);
+        }
+    }
+
+    /// Returns the number of explored cells.
+    pub fn explored_count(&self) -> usize {
+        self.cells.iter().filter(|&&explored| explored).count()
+    }
+
+    /// Forgets everything, e.g. when entering a new level.
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = false;
+        }
+    }
+
+    /// Dims the remembered cells that aren't in `map`'s field of view and hides the cells that
+    /// were never seen. The console's cells are matched with the map's cells from the top left
+    /// corner.
+    pub fn render_fog<C: Console>(&self, console: &mut C, map: &Map, style: &FogStyle) {
+        self.render_fog_with(console, |x, y| map.is_in_fov(x, y), style);
+    }
+
+    /// Like `render_fog`, with the visible cells given by `is_visible(x, y)`.
+    pub fn render_fog_with<C, F>(&self, console: &mut C, is_visible: F, style: &FogStyle)
+        where C: Console, F: Fn(i32, i32) -> bool
+    {
+        let width = self.width.min(console.width());
+        let height = self.height.min(console.height());
+        for y in 0..height {
+            for x in 0..width {
+                if is_visible(x, y) {
+                    continue;
+                }
+                if self.is_explored(x, y) {
+                    let background = colors::lerp(console.get_char_background(x, y),
+                                                  style.remembered, style.amount);
+                    let foreground = colors::lerp(console.get_char_foreground(x, y),
+                                                  style.remembered, style.amount);
+                    console.set_char_background(x, y, background, BackgroundFlag::Set);
+                    console.set_char_foreground(x, y, foreground);
+                } else {
+                    console.put_char_ex(x, y, ' ', style.unexplored, style.unexplored);
+                }
+            }
+        }
+    }
+}
+
+/// How `ExploredMap::render_fog` draws the cells out of sight
+#[derive(Copy, Clone, Debug)]
+pub struct FogStyle {
+    /// The color the remembered cells are blended with
+    pub remembered: Color,
+    /// How much of `remembered` is blended in, from 0.0 to 1.0
+    pub amount: f32,
+    /// The color of the cells that were never seen
+    pub unexplored: Color,
+}
+
+impl Default for FogStyle {
+    /// Remembered cells at half their brightness, unexplored ones black.
+    fn default() -> FogStyle {
+        FogStyle {
+            remembered: Color::new(0, 0, 0),
+            amount: 0.5,
+            unexplored: Color::new(0, 0, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ExploredMap;
+    use fov;
+
+    #[test]
+    fn accumulate() {
+        let mut explored = ExploredMap::new(10, 1);
+        explored.update_from(&fov::compute_symmetric(10, 1, (1, 0), 2, true, |_, _| false));
+        explored.update_from(&fov::compute_symmetric(10, 1, (8, 0), 1, true, |_, _| false));
+        let cells: Vec<bool> = (0..10).map(|x| explored.is_explored(x, 0)).collect();
+        assert_eq!(cells, [true, true, true, true, false, false, false, true, true, true]);
+        assert_eq!(explored.explored_count(), 7);
+        explored.clear();
+        assert_eq!(explored.explored_count(), 0);
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn serialization() {
+        use serde_json;
+
+        let mut explored = ExploredMap::new(3, 2);
+        explored.set_explored(2, 1, true);
+        let encoded = serde_json::to_string(&explored).unwrap();
+        assert_eq!(serde_json::from_str::<ExploredMap>(&encoded).unwrap(), explored);
+    }
+}
@@ -0,0 +1,556 @@
+//! Field of view computed in Rust
+//!
+//! [compute_symmetric](./fn.compute_symmetric.html) implements symmetric shadowcasting: a floor
+//! cell is visible from another exactly when the second is visible from the first, which none of
+//! libtcod's [FovAlgorithm](../map/enum.FovAlgorithm.html)s guarantee. Stealth and ranged attacks
+//! can then rely on "if I see you, you see me".
+//!
+//! It works on any opacity source, not only a `Map`:
+//!
+//! ```
+//! use tcod::fov;
+//!
+//! let walls = ["#######",
+//!              "#.....#",
+//!              "#..#..#",
+//!              "#.....#",
+//!              "#######"];
+//! let visible = fov::compute_symmetric(7, 5, (1, 2), 0, true, |x, y| {
+//!     walls[y as usize].as_bytes()[x as usize] == b'#'
+//! });
+//! assert!(visible.is_visible(2, 2));
+//! assert!(!visible.is_visible(5, 2)); // behind the pillar
+//! assert!(visible.is_visible(0, 0)); // walls are lit
+//! ```
+//!
+//! Where nothing blocks the sight, the result is the same as libtcod's `FovAlgorithm::Shadow`.
+//! They differ in the shadows of opaque cells: `Shadow` lights more of the narrow shadow behind
+//! a pillar (and from some of these cells, `Shadow` doesn't see the origin back), while the
+//! symmetric version sees some cells through diagonal gaps that `Shadow` keeps dark. Neither
+//! result contains the other in general.
+//!
+//! `Map::compute_fov_symmetric` stores the result in the map like `Map::compute_fov` does.
+//!
+//! [compute_partial](./fn.compute_partial.html) lets cells block the sight partially, e.g. smoke
+//! or tall grass, and returns how well each cell can be seen. An
+//! [OpacityMap](./struct.OpacityMap.html) stores the opacity of every cell for it.
+
+use map::Map;
+
+/// The cells visible from an origin
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Visibility {
+    width: i32,
+    height: i32,
+    cells: Vec<bool>,
+}
+
+impl Visibility {
+    /// Creates a `width` x `height` area with nothing visible.
+    pub fn new(width: i32, height: i32) -> Visibility {
+        assert!(width > 0 && height > 0);
+        Visibility { width, height, cells: vec![false; (width * height) as usize] }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    /// Returns whether a cell is visible. Cells outside the area aren't.
+    pub fn is_visible(&self, x: i32, y: i32) -> bool {
+        self.contains(x, y) && self.cells[(y * self.width + x) as usize]
+    }
+
+    pub fn set_visible(&mut self, x: i32, y: i32, visible: bool) {
+        assert!(self.contains(x, y));
+        self.cells[(y * self.width + x) as usize] = visible;
+    }
+
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = false;
+        }
+    }
+
+    /// Iterates over the visible cells, row by row.
+    pub fn visible_cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let width = self.width;
+        self.cells.iter().enumerate()
+            .filter(|&(_, &visible)| visible)
+            .map(move |(index, _)| (index as i32 % width, index as i32 / width))
+    }
+}
+
+/// Returns whether `to` is visible from `from` with symmetric shadowcasting and lit walls, i.e.
+/// whether it's in the result of `compute_symmetric` with `light_walls`.
+///
+/// Only the cells in the rectangle between `from` and `to` are looked at, since the others can't
+/// cast a shadow on `to`. This makes it cheap enough to check the sight of many monsters.
+pub fn is_visible_symmetric<F>(from: (i32, i32), to: (i32, i32), is_opaque: F) -> bool
+    where F: Fn(i32, i32) -> bool
+{
+    let (left, top) = (from.0.min(to.0), from.1.min(to.1));
+    let width = (to.0 - from.0).abs() + 1;
+    let height = (to.1 - from.1).abs() + 1;
+    let visibility = compute_symmetric(width, height, (from.0 - left, from.1 - top), 0, true,
+                                       |x, y| is_opaque(x + left, y + top));
+    visibility.is_visible(to.0 - left, to.1 - top)
+}
+
+/// Returns whether the offset (`dx`, `dy`) lies within `half_angle` of the `facing_angle`
+/// direction.
+///
+/// The angles are in radians, measured from the positive x axis towards the positive y axis, so
+/// with y growing downwards, `PI / 2.0` faces down. A zero offset is always in the cone and a
+/// `half_angle` of `PI` or more covers every direction.
+pub fn is_in_cone(dx: i32, dy: i32, facing_angle: f32, half_angle: f32) -> bool {
+    if (dx, dy) == (0, 0) || half_angle >= ::std::f32::consts::PI {
+        return true;
+    }
+    let angle = (dy as f32).atan2(dx as f32);
+    let difference = (angle - facing_angle).sin().atan2((angle - facing_angle).cos());
+    difference.abs() <= half_angle + 1e-4
+}
+
+/// A slope of the form `numerator / denominator`, with a positive denominator
+#[derive(Copy, Clone, Debug)]
+struct Slope {
+    numerator: i32,
+    denominator: i32,
+}
+
+impl Slope {
+    /// The slope going through the left edge of `column` at `depth`
+    fn of_edge(depth: i32, column: i32) -> Slope {
+        Slope { numerator: 2 * column - 1, denominator: 2 * depth }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Row {
+    depth: i32,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    fn next(&self) -> Row {
+        Row { depth: self.depth + 1, ..*self }
+    }
+
+    /// The first column of the row: `depth * start` rounded with ties going up.
+    fn min_column(&self) -> i32 {
+        let Slope { numerator, denominator } = self.start;
+        (2 * self.depth * numerator + denominator).div_euclid(2 * denominator)
+    }
+
+    /// The last column of the row: `depth * end` rounded with ties going down.
+    fn max_column(&self) -> i32 {
+        let Slope { numerator, denominator } = self.end;
+        -(-2 * self.depth * numerator + denominator).div_euclid(2 * denominator)
+    }
+
+    /// Whether the center of the floor cell at `column` is within the row's slopes.
+    fn is_symmetric(&self, column: i32) -> bool {
+        column * self.start.denominator >= self.depth * self.start.numerator &&
+            column * self.end.denominator <= self.depth * self.end.numerator
+    }
+}
+
+/// Turns a (depth, column) position in one of the four quadrants into map coordinates.
+fn transform((origin_x, origin_y): (i32, i32), quadrant: u8, depth: i32, column: i32) -> (i32, i32) {
+    match quadrant {
+        0 => (origin_x + column, origin_y - depth),
+        1 => (origin_x + depth, origin_y + column),
+        2 => (origin_x + column, origin_y + depth),
+        _ => (origin_x - depth, origin_y + column),
+    }
+}
+
+/// Computes the cells of a `width` x `height` area visible from `origin` with symmetric
+/// shadowcasting.
+///
+/// `is_opaque(x, y)` tells which cells block the sight. It's only called for cells inside the
+/// area, whose edges also block the sight. A `max_radius` of 0 means the sight isn't limited.
+/// With `light_walls`, the opaque cells bordering the visible area are visible too.
+pub fn compute_symmetric<F>(width: i32, height: i32, origin: (i32, i32), max_radius: i32,
+                            light_walls: bool, is_opaque: F) -> Visibility
+    where F: Fn(i32, i32) -> bool
+{
+    let mut visibility = Visibility::new(width, height);
+    assert!(visibility.contains(origin.0, origin.1));
+    assert!(max_radius >= 0);
+    visibility.set_visible(origin.0, origin.1, true);
+
+    let in_radius = |depth: i32, column: i32| {
+        max_radius == 0 || depth * depth + column * column <= max_radius * max_radius
+    };
+    for quadrant in 0..4 {
+        let mut rows = vec![Row {
+            depth: 1,
+            start: Slope { numerator: -1, denominator: 1 },
+            end: Slope { numerator: 1, denominator: 1 },
+        }];
+        while let Some(mut row) = rows.pop() {
+            if max_radius > 0 && row.depth > max_radius {
+                continue;
+            }
+            let mut previous_opaque = None;
+            for column in row.min_column()..row.max_column() + 1 {
+                let (x, y) = transform(origin, quadrant, row.depth, column);
+                let inside = visibility.contains(x, y);
+                let opaque = !inside || is_opaque(x, y);
+                if inside && in_radius(row.depth, column) &&
+                    (opaque && light_walls || !opaque && row.is_symmetric(column)) {
+                    visibility.set_visible(x, y, true);
+                }
+                if previous_opaque == Some(true) && !opaque {
+                    row.start = Slope::of_edge(row.depth, column);
+                }
+                if previous_opaque == Some(false) && opaque {
+                    let mut next = row.next();
+                    next.end = Slope::of_edge(row.depth, column);
+                    rows.push(next);
+                }
+                previous_opaque = Some(opaque);
+            }
+            if previous_opaque == Some(false) {
+                rows.push(row.next());
+            }
+        }
+    }
+    visibility
+}
+
+/// How well the cells of an area can be seen, from 0.0 (not at all) to 1.0 (clearly)
+#[derive(Clone, PartialEq, Debug)]
+pub struct VisibilityLevels {
+    width: i32,
+    height: i32,
+    levels: Vec<f32>,
+}
+
+impl VisibilityLevels {
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Returns how well a cell can be seen. Cells outside the area can't be seen at all.
+    pub fn level(&self, x: i32, y: i32) -> f32 {
+        if x >= 0 && y >= 0 && x < self.width && y < self.height {
+            self.levels[(y * self.width + x) as usize]
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns whether a cell can be seen at least `threshold` well.
+    pub fn is_visible(&self, x: i32, y: i32, threshold: f32) -> bool {
+        let level = self.level(x, y);
+        level > 0.0 && level >= threshold
+    }
+
+    /// Turns the levels into a `Visibility` with the cells seen at least `threshold` well.
+    pub fn to_visibility(&self, threshold: f32) -> Visibility {
+        let mut visibility = Visibility::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.is_visible(x, y, threshold) {
+                    visibility.set_visible(x, y, true);
+                }
+            }
+        }
+        visibility
+    }
+}
+
+/// The opacity of every cell of an area, from 0.0 (clear air) to 1.0 (a wall)
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct OpacityMap {
+    width: i32,
+    height: i32,
+    cells: Vec<f32>,
+}
+
+impl OpacityMap {
+    /// Creates a `width` x `height` area where everything is clear.
+    pub fn new(width: i32, height: i32) -> OpacityMap {
+        assert!(width > 0 && height > 0);
+        OpacityMap { width, height, cells: vec![0.0; (width * height) as usize] }
+    }
+
+    /// Creates an opacity map with the transparent cells of `map` clear and the others opaque.
+    pub fn from_map(map: &Map) -> OpacityMap {
+        let (width, height) = map.size();
+        let mut opacities = OpacityMap::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if !map.is_transparent(x, y) {
+                    opacities.set_opacity(x, y, 1.0);
+                }
+            }
+        }
+        opacities
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        assert!(x >= 0 && y >= 0 && x < self.width && y < self.height);
+        (y * self.width + x) as usize
+    }
+
+    pub fn opacity(&self, x: i32, y: i32) -> f32 {
+        self.cells[self.index(x, y)]
+    }
+
+    /// Sets the opacity of a cell, e.g. 0.3 for smoke or 0.6 for tall grass. It's clamped to the
+    /// 0.0 to 1.0 range.
+    pub fn set_opacity(&mut self, x: i32, y: i32, opacity: f32) {
+        let index = self.index(x, y);
+        self.cells[index] = opacity.clamp(0.0, 1.0);
+    }
+
+    /// Computes how well every cell can be seen from `origin`. See `compute_partial`.
+    pub fn compute_fov(&self, origin: (i32, i32), max_radius: i32) -> VisibilityLevels {
+        compute_partial(self.width, self.height, origin, max_radius, |x, y| self.opacity(x, y))
+    }
+}
+
+/// The cells of a Bresenham line from `start` to `end`, both included.
+fn bresenham((mut x, mut y): (i32, i32), (end_x, end_y): (i32, i32)) -> Vec<(i32, i32)> {
+    let (dx, dy) = ((end_x - x).abs(), -(end_y - y).abs());
+    let (step_x, step_y) = ((end_x - x).signum(), (end_y - y).signum());
+    let mut error = dx + dy;
+    let mut cells = vec![(x, y)];
+    while (x, y) != (end_x, end_y) {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+        cells.push((x, y));
+    }
+    cells
+}
+
+/// Computes how well the cells of a `width` x `height` area can be seen from `origin` through
+/// partially opaque cells such as smoke, foliage or dirty glass.
+///
+/// `opacity(x, y)` returns how much of the sight a cell blocks, from 0.0 to 1.0. Along the line to
+/// a cell, the visibility is multiplied by `1.0 - opacity` of every cell it crosses, so a cell
+/// behind two cells of 0.5 smoke has a level of 0.25. The opacity of a cell doesn't affect its own
+/// level, which makes walls and the front of a smoke cloud visible. The levels are symmetric: a
+/// cell sees another exactly as well as it's seen by it.
+///
+/// A `max_radius` of 0 means the sight isn't limited.
+pub fn compute_partial<F>(width: i32, height: i32, origin: (i32, i32), max_radius: i32,
+                          opacity: F) -> VisibilityLevels
+    where F: Fn(i32, i32) -> f32
+{
+    assert!(width > 0 && height > 0);
+    assert!(origin.0 >= 0 && origin.1 >= 0 && origin.0 < width && origin.1 < height);
+    assert!(max_radius >= 0);
+    let transmittance = |cells: &[(i32, i32)]| {
+        let inner = if cells.len() > 2 { &cells[1..cells.len() - 1] } else { &[] };
+        inner.iter()
+            .fold(1.0, |level, &(x, y)| level * (1.0 - opacity(x, y).clamp(0.0, 1.0)))
+    };
+    let mut levels = vec![0.0; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x - origin.0, y - origin.1);
+            if max_radius > 0 && dx * dx + dy * dy > max_radius * max_radius {
+                continue;
+            }
+            // Bresenham lines aren't the same in both directions, take the clearer one
+            let there = transmittance(&bresenham(origin, (x, y)));
+            let back = transmittance(&bresenham((x, y), origin));
+            levels[(y * width + x) as usize] = f32::max(there, back);
+        }
+    }
+    VisibilityLevels { width, height, levels }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bresenham, compute_partial, compute_symmetric, is_visible_symmetric, OpacityMap};
+
+    fn parse(rows: &[&str]) -> (i32, i32, Vec<Vec<bool>>) {
+        let walls: Vec<Vec<bool>> = rows.iter()
+            .map(|row| row.bytes().map(|b| b == b'#').collect())
+            .collect();
+        (rows[0].len() as i32, rows.len() as i32, walls)
+    }
+
+    #[test]
+    fn open_room() {
+        let visibility = compute_symmetric(10, 8, (3, 3), 0, true, |_, _| false);
+        assert_eq!(visibility.visible_cells().count(), 80);
+        let visibility = compute_symmetric(21, 21, (10, 10), 3, true, |_, _| false);
+        assert!(visibility.is_visible(13, 10) && visibility.is_visible(12, 12));
+        assert!(!visibility.is_visible(13, 12) && !visibility.is_visible(14, 10));
+    }
+
+    #[test]
+    fn walls_and_shadows() {
+        let (width, height, walls) = parse(&["##########",
+                                             "#........#",
+                                             "#...#....#",
+                                             "#........#",
+                                             "##########"]);
+        let opaque = |x: i32, y: i32| walls[y as usize][x as usize];
+        let visibility = compute_symmetric(width, height, (2, 2), 0, true, opaque);
+        assert!(visibility.is_visible(3, 2) && visibility.is_visible(4, 2));
+        assert!(!visibility.is_visible(5, 2) && !visibility.is_visible(8, 2));
+        assert!(visibility.is_visible(6, 1) && visibility.is_visible(8, 0));
+        assert!(!visibility.is_visible(8, 1) && !visibility.is_visible(8, 3));
+
+        let visibility = compute_symmetric(width, height, (2, 2), 0, false, opaque);
+        assert!(!visibility.is_visible(4, 2) && !visibility.is_visible(0, 0));
+        assert!(visibility.is_visible(6, 3));
+    }
+
+    #[test]
+    fn symmetry() {
+        // A pseudo-random cave
+        let (width, height) = (24, 16);
+        let mut seed = 12345u32;
+        let walls: Vec<bool> = (0..width * height).map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) & 3 == 0
+        }).collect();
+        let opaque = |x: i32, y: i32| walls[(y * width + x) as usize];
+        let floors: Vec<(i32, i32)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| !opaque(x, y))
+            .collect();
+        let views: Vec<_> = floors.iter()
+            .map(|&origin| compute_symmetric(width, height, origin, 0, true, opaque))
+            .collect();
+        for (a, view_a) in floors.iter().zip(&views) {
+            for (b, view_b) in floors.iter().zip(&views) {
+                assert_eq!(view_a.is_visible(b.0, b.1), view_b.is_visible(a.0, a.1),
+                           "{:?} and {:?}", a, b);
+            }
+        }
+        // Looking at the cells between two cells only gives the same result
+        for (a, view_a) in floors.iter().zip(&views) {
+            for y in 0..height {
+                for x in 0..width {
+                    assert_eq!(is_visible_symmetric(*a, (x, y), opaque), view_a.is_visible(x, y),
+                               "{:?} and {:?}", a, (x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(bresenham((0, 0), (3, 1)), [(0, 0), (1, 0), (2, 1), (3, 1)]);
+        assert_eq!(bresenham((2, 2), (2, 0)), [(2, 2), (2, 1), (2, 0)]);
+        assert_eq!(bresenham((1, 1), (1, 1)), [(1, 1)]);
+    }
+
+    #[test]
+    fn partial_opacity() {
+        let mut opacities = OpacityMap::new(10, 3);
+        opacities.set_opacity(3, 1, 0.5);
+        opacities.set_opacity(4, 1, 0.5);
+        opacities.set_opacity(7, 0, 1.0);
+        opacities.set_opacity(7, 1, 1.0);
+        opacities.set_opacity(7, 2, 1.0);
+        let levels = opacities.compute_fov((1, 1), 0);
+        assert_eq!(levels.level(1, 1), 1.0);
+        assert_eq!(levels.level(3, 1), 1.0);
+        assert_eq!(levels.level(4, 1), 0.5);
+        assert_eq!(levels.level(5, 1), 0.25);
+        // The wall is visible, what's behind it isn't
+        assert_eq!(levels.level(7, 1), 0.25);
+        assert_eq!(levels.level(8, 1), 0.0);
+        assert!(levels.is_visible(5, 1, 0.2) && !levels.is_visible(5, 1, 0.3));
+        assert!(!levels.to_visibility(0.1).is_visible(9, 0));
+
+        let limited = opacities.compute_fov((1, 1), 2);
+        assert_eq!(limited.level(3, 1), 1.0);
+        assert_eq!(limited.level(4, 1), 0.0);
+
+        // Symmetric in both directions
+        for &(x, y) in &[(5, 0), (6, 2), (0, 2)] {
+            let back = compute_partial(10, 3, (x, y), 0, |x, y| opacities.opacity(x, y));
+            assert_eq!(levels.level(x, y), back.level(1, 1));
+        }
+    }
+
+    #[test]
+    fn cones() {
+        use super::is_in_cone;
+        use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+        assert!(is_in_cone(5, 0, 0.0, FRAC_PI_4));
+        assert!(is_in_cone(5, 5, 0.0, FRAC_PI_4));
+        assert!(!is_in_cone(5, 6, 0.0, FRAC_PI_4));
+        assert!(!is_in_cone(-5, 0, 0.0, FRAC_PI_4));
+        // Facing down, across the -PI/PI boundary and all around
+        assert!(is_in_cone(0, 3, FRAC_PI_2, 0.1));
+        assert!(is_in_cone(-4, -1, PI, FRAC_PI_4) && is_in_cone(-4, 1, -PI, FRAC_PI_4));
+        assert!(is_in_cone(-4, 0, 0.0, PI));
+        assert!(is_in_cone(0, 0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn compared_with_libtcod_shadow() {
+        use map::{FovAlgorithm, Map};
+
+        let mut map = Map::new(20, 15);
+        map.clear(true, true);
+        let compute = |map: &mut Map| {
+            map.compute_fov(5, 5, 0, true, FovAlgorithm::Shadow);
+            compute_symmetric(20, 15, (5, 5), 0, true, |x, y| !map.is_transparent(x, y))
+        };
+
+        // The same in the open
+        let visibility = compute(&mut map);
+        for y in 0..15 {
+            for x in 0..20 {
+                assert_eq!(visibility.is_visible(x, y), map.is_in_fov(x, y), "({}, {})", x, y);
+            }
+        }
+
+        // libtcod lights more of the pillar's shadow
+        map.set(12, 6, false, false);
+        let visibility = compute(&mut map);
+        let mut differences = vec![];
+        for y in 0..15 {
+            for x in 0..20 {
+                if visibility.is_visible(x, y) {
+                    assert!(map.is_in_fov(x, y), "({}, {})", x, y);
+                } else if map.is_in_fov(x, y) {
+                    differences.push((x, y));
+                }
+            }
+        }
+        let behind_pillar: Vec<_> = (13..19).map(|x| (x, 6)).chain((15..17).map(|x| (x, 7)))
+            .collect();
+        assert_eq!(differences, behind_pillar);
+    }
+}
@@ -1,15 +1,26 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
+use std::error;
+use std::fmt;
+use std::hash::Hash;
 use std::mem;
-use std::str;
+use std::str::{self, FromStr};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use bindings::ffi;
-use bindings::{CStr, c_bool, c_char, c_uint, keycode_from_native};
+use bindings::{CStr, c_bool, c_char, c_int, c_uint, keycode_from_native};
 
 
+/// How often `wait_for_event` checks for new events when it has a timeout.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
 /// Deprecated. Use `tcod::input::Mouse` instead.
 pub type MouseState = Mouse;
 
 #[repr(u32)]
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub enum KeyCode {
     NoKey = ffi::TCOD_keycode_t::TCODK_NONE as u32,
     Escape = ffi::TCOD_keycode_t::TCODK_ESCAPE as u32,
@@ -82,6 +93,69 @@ pub enum KeyCode {
     Text = ffi::TCOD_keycode_t::TCODK_TEXT as u32,
 }
 
+static KEY_CODE_NAMES: [(KeyCode, &str); 67] = [
+    (KeyCode::NoKey, "NoKey"), (KeyCode::Escape, "Escape"), (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Tab, "Tab"), (KeyCode::Enter, "Enter"), (KeyCode::Shift, "Shift"),
+    (KeyCode::Control, "Control"), (KeyCode::Alt, "Alt"), (KeyCode::Pause, "Pause"),
+    (KeyCode::CapsLock, "CapsLock"), (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"), (KeyCode::End, "End"), (KeyCode::Home, "Home"),
+    (KeyCode::Up, "Up"), (KeyCode::Left, "Left"), (KeyCode::Right, "Right"),
+    (KeyCode::Down, "Down"), (KeyCode::PrintScreen, "PrintScreen"),
+    (KeyCode::Insert, "Insert"), (KeyCode::Delete, "Delete"), (KeyCode::LeftWin, "LeftWin"),
+    (KeyCode::RightWin, "RightWin"), (KeyCode::Apps, "Apps"),
+    (KeyCode::Number0, "Number0"), (KeyCode::Number1, "Number1"),
+    (KeyCode::Number2, "Number2"), (KeyCode::Number3, "Number3"),
+    (KeyCode::Number4, "Number4"), (KeyCode::Number5, "Number5"),
+    (KeyCode::Number6, "Number6"), (KeyCode::Number7, "Number7"),
+    (KeyCode::Number8, "Number8"), (KeyCode::Number9, "Number9"),
+    (KeyCode::NumPad0, "NumPad0"), (KeyCode::NumPad1, "NumPad1"),
+    (KeyCode::NumPad2, "NumPad2"), (KeyCode::NumPad3, "NumPad3"),
+    (KeyCode::NumPad4, "NumPad4"), (KeyCode::NumPad5, "NumPad5"),
+    (KeyCode::NumPad6, "NumPad6"), (KeyCode::NumPad7, "NumPad7"),
+    (KeyCode::NumPad8, "NumPad8"), (KeyCode::NumPad9, "NumPad9"),
+    (KeyCode::NumPadAdd, "NumPadAdd"), (KeyCode::NumPadSubtract, "NumPadSubtract"),
+    (KeyCode::NumPadDivide, "NumPadDivide"), (KeyCode::NumPadMultiply, "NumPadMultiply"),
+    (KeyCode::NumPadDecimal, "NumPadDecimal"), (KeyCode::NumPadEnter, "NumPadEnter"),
+    (KeyCode::F1, "F1"), (KeyCode::F2, "F2"), (KeyCode::F3, "F3"), (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"), (KeyCode::F6, "F6"), (KeyCode::F7, "F7"), (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"), (KeyCode::F10, "F10"), (KeyCode::F11, "F11"), (KeyCode::F12, "F12"),
+    (KeyCode::NumLock, "NumLock"), (KeyCode::ScrollLock, "ScrollLock"),
+    (KeyCode::Spacebar, "Spacebar"), (KeyCode::Char, "Char"), (KeyCode::Text, "Text"),
+];
+
+impl fmt::Display for KeyCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let &(_, name) = KEY_CODE_NAMES.iter().find(|&&(code, _)| code == *self).unwrap();
+        f.write_str(name)
+    }
+}
+
+impl FromStr for KeyCode {
+    type Err = ParseKeyError;
+
+    /// Parses the name of a `KeyCode` variant, ignoring the case.
+    fn from_str(s: &str) -> Result<KeyCode, ParseKeyError> {
+        KEY_CODE_NAMES.iter()
+            .find(|&&(_, name)| name.eq_ignore_ascii_case(s))
+            .map(|&(code, _)| code)
+            .ok_or_else(|| ParseKeyError { input: s.to_owned() })
+    }
+}
+
+/// An error returned when parsing a `KeyCode` or a `KeyChord` fails
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseKeyError {
+    input: String,
+}
+
+impl fmt::Display for ParseKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown key: {}", self.input)
+    }
+}
+
+impl error::Error for ParseKeyError {}
+
 impl Default for KeyCode {
     fn default() -> Self {
         KeyCode::NoKey
@@ -89,6 +163,7 @@ impl Default for KeyCode {
 }
 
 #[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub struct Key {
     pub code: KeyCode,
     pub printable: char,
@@ -101,6 +176,7 @@ pub struct Key {
     pub alt: bool,
     pub ctrl: bool,
 
+    #[cfg_attr(feature = "serialization", serde(with = "key_text"))]
     text: [c_char; 32],
 }
 
@@ -110,6 +186,36 @@ impl Key {
             CStr::from_ptr(&self.text[0] as *const c_char).to_str().unwrap()
         }
     }
+
+    /// Sets the text of a `KeyCode::Text` event. Text longer than 31 bytes is truncated.
+    pub fn set_text(&mut self, text: &str) {
+        let mut len = text.len().min(self.text.len() - 1);
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.text = [0; 32];
+        for (dest, &byte) in self.text.iter_mut().zip(&text.as_bytes()[..len]) {
+            *dest = byte as c_char;
+        }
+    }
+}
+
+/// Serializes the text of a `Key` as a string.
+#[cfg(feature = "serialization")]
+mod key_text {
+    use bindings::{CStr, c_char};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(text: &[c_char; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        let text = unsafe { CStr::from_ptr(text.as_ptr()) };
+        serializer.serialize_str(&text.to_string_lossy())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[c_char; 32], D::Error> {
+        let mut key = super::Key::default();
+        key.set_text(&String::deserialize(deserializer)?);
+        Ok(key.text)
+    }
 }
 
 impl From<ffi::TCOD_key_t> for Key {
@@ -130,7 +236,165 @@ impl From<ffi::TCOD_key_t> for Key {
     }
 }
 
+impl From<Key> for ffi::TCOD_key_t {
+    fn from(key: Key) -> ffi::TCOD_key_t {
+        ffi::TCOD_key_t {
+            vk: unsafe { mem::transmute::<u32, ffi::TCOD_keycode_t>(key.code as u32) },
+            c: if (key.printable as u32) < 256 { key.printable as u8 as c_char } else { 0 },
+            text: key.text,
+            pressed: key.pressed as c_bool,
+            lalt: key.left_alt as c_bool,
+            lctrl: key.left_ctrl as c_bool,
+            lmeta: 0,
+            ralt: key.right_alt as c_bool,
+            rctrl: key.right_ctrl as c_bool,
+            rmeta: 0,
+            shift: key.shift as c_bool,
+        }
+    }
+}
+
+/// A key combined with the Ctrl, Alt and Shift modifiers
+///
+/// Chords are written as the modifiers followed by the key, joined with `+`: `"Ctrl+Shift+F5"`,
+/// `"Alt+x"` or `"Ctrl++"`. A single character stands for the key producing it (with
+/// `KeyCode::Char`), anything else is a `KeyCode` name. Note that libtcod reports the digits of
+/// the main keyboard as `Number0` to `Number9` rather than `Char`. Parsing ignores the case of
+/// the modifiers and key names, printing uses the form above.
+///
+/// ```
+/// use tcod::input::{KeyChord, KeyCode};
+///
+/// let chord: KeyChord = "ctrl+shift+f5".parse().unwrap();
+/// assert_eq!(chord, KeyChord::new(KeyCode::F5).ctrl().shift());
+/// assert_eq!(chord.to_string(), "Ctrl+Shift+F5");
+/// assert_eq!("Alt+x".parse(), Ok(KeyChord::char('x').alt()));
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    /// The character of a `KeyCode::Char` key, `'\0'` for other keys
+    pub printable: char,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode) -> KeyChord {
+        KeyChord { code, printable: '\0', ctrl: false, alt: false, shift: false }
+    }
+
+    /// A chord of the key producing `printable`.
+    pub fn char(printable: char) -> KeyChord {
+        KeyChord { printable, ..KeyChord::new(KeyCode::Char) }
+    }
+
+    pub fn ctrl(mut self) -> KeyChord {
+        self.ctrl = true;
+        self
+    }
+
+    pub fn alt(mut self) -> KeyChord {
+        self.alt = true;
+        self
+    }
+
+    pub fn shift(mut self) -> KeyChord {
+        self.shift = true;
+        self
+    }
+
+    /// Returns whether a key event is this chord, with exactly the same modifiers held down.
+    /// Whether the key was pressed or released doesn't matter.
+    pub fn matches(&self, key: &Key) -> bool {
+        KeyChord::from(*key) == *self
+    }
+}
+
+impl From<Key> for KeyChord {
+    fn from(key: Key) -> KeyChord {
+        KeyChord {
+            code: key.code,
+            printable: if key.code == KeyCode::Char { key.printable } else { '\0' },
+            ctrl: key.ctrl,
+            alt: key.alt,
+            shift: key.shift,
+        }
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(held, name) in &[(self.ctrl, "Ctrl+"), (self.alt, "Alt+"), (self.shift, "Shift+")] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        if self.code == KeyCode::Char && self.printable != '\0' {
+            write!(f, "{}", self.printable)
+        } else {
+            write!(f, "{}", self.code)
+        }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = ParseKeyError;
+
+    fn from_str(s: &str) -> Result<KeyChord, ParseKeyError> {
+        let mut chord = KeyChord::new(KeyCode::NoKey);
+        let mut rest = s.trim();
+        loop {
+            let prefix = rest.find('+').map(|index| &rest[..index]);
+            let modifier = match prefix {
+                // The last part is the key, even if it's '+' itself
+                Some(prefix) if prefix.len() + 1 < rest.len() => prefix,
+                _ => break,
+            };
+            if modifier.eq_ignore_ascii_case("ctrl") {
+                chord.ctrl = true;
+            } else if modifier.eq_ignore_ascii_case("alt") {
+                chord.alt = true;
+            } else if modifier.eq_ignore_ascii_case("shift") {
+                chord.shift = true;
+            } else {
+                return Err(ParseKeyError { input: s.to_owned() });
+            }
+            rest = &rest[modifier.len() + 1..];
+        }
+
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(printable), None) => {
+                chord.code = KeyCode::Char;
+                chord.printable = printable;
+            }
+            _ => {
+                chord.code = rest.parse().map_err(|_| ParseKeyError { input: s.to_owned() })?;
+            }
+        }
+        Ok(chord)
+    }
+}
+
+#[cfg(feature = "serialization")]
+impl ::serde::Serialize for KeyChord {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serialization")]
+impl<'de> ::serde::Deserialize<'de> for KeyChord {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let chord = String::deserialize(deserializer)?;
+        chord.parse().map_err(::serde::de::Error::custom)
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub struct Mouse {
     pub x: isize,
     pub y: isize,
@@ -150,6 +414,171 @@ pub struct Mouse {
     pub wheel_down: bool,
 }
 
+impl From<ffi::TCOD_mouse_t> for Mouse {
+    fn from(tcod_mouse: ffi::TCOD_mouse_t) -> Mouse {
+        Mouse {
+            x: tcod_mouse.x as isize,
+            y: tcod_mouse.y as isize,
+            dx: tcod_mouse.dx as isize,
+            dy: tcod_mouse.dy as isize,
+            cx: tcod_mouse.cx as isize,
+            cy: tcod_mouse.cy as isize,
+            dcx: tcod_mouse.dcx as isize,
+            dcy: tcod_mouse.dcy as isize,
+            lbutton: tcod_mouse.lbutton != 0,
+            rbutton: tcod_mouse.rbutton != 0,
+            mbutton: tcod_mouse.mbutton != 0,
+            lbutton_pressed: tcod_mouse.lbutton_pressed != 0,
+            rbutton_pressed: tcod_mouse.rbutton_pressed != 0,
+            mbutton_pressed: tcod_mouse.mbutton_pressed != 0,
+            wheel_up: tcod_mouse.wheel_up != 0,
+            wheel_down: tcod_mouse.wheel_down != 0
+        }
+    }
+}
+
+/// What a finger did
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum TouchPhase {
+    /// The finger touched the screen
+    Press,
+    /// The finger moved while touching the screen
+    Move,
+    /// The finger was lifted
+    Release,
+}
+
+/// Where a finger is, in pixels and in console cells
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct TouchPosition {
+    pub x: isize,
+    pub y: isize,
+    pub dx: isize,
+    pub dy: isize,
+    pub cx: isize,
+    pub cy: isize,
+    pub dcx: isize,
+    pub dcy: isize,
+}
+
+/// A touch screen event
+///
+/// The position comes from libtcod's own finger tracking, so it's there for every finger and
+/// for the whole touch, whether or not the touch is also passed on to the mouse (see
+/// [mouse_includes_touch](./fn.mouse_includes_touch.html)). `finger` counts the fingers of a
+/// touch from 0: it restarts when all of them have been lifted.
+///
+/// libtcod only handles touches on Android, so there are no touch events elsewhere.
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct Touch {
+    pub phase: TouchPhase,
+    pub finger: usize,
+    pub position: TouchPosition,
+}
+
+impl Touch {
+    fn from_native(flags: EventFlags, (finger, position): (usize, TouchPosition)) -> Touch {
+        let phase = if flags.contains(FINGER_PRESS) {
+            TouchPhase::Press
+        } else if flags.contains(FINGER_RELEASE) {
+            TouchPhase::Release
+        } else {
+            TouchPhase::Move
+        };
+        Touch { phase, finger, position }
+    }
+}
+
+/// Returns the finger of the last touch event and where it is, as libtcod tracks them.
+fn native_touch_finger() -> (usize, TouchPosition) {
+    let (mut finger, mut x, mut y, mut dx, mut dy) = (0, 0, 0, 0, 0);
+    let (mut cx, mut cy, mut dcx, mut dcy) = (0, 0, 0, 0);
+    let found = unsafe {
+        ffi::TCOD_mouse_get_touch_finger(&mut finger, &mut x, &mut y, &mut dx, &mut dy,
+                                         &mut cx, &mut cy, &mut dcx, &mut dcy) != 0
+    };
+    if !found {
+        return (0, TouchPosition::default());
+    }
+    (finger as usize, TouchPosition {
+        x: x as isize,
+        y: y as isize,
+        dx: dx as isize,
+        dy: dy as isize,
+        cx: cx as isize,
+        cy: cy as isize,
+        dcx: dcx as isize,
+        dcy: dcy as isize,
+    })
+}
+
+thread_local! {
+    /// The mouse event emulated for a touch, returned after the touch event
+    static PENDING_SDL_EVENT: Cell<Option<(EventFlags, Event)>> = const { Cell::new(None) };
+}
+
+/// Returns the event left over from the previous SDL event, if it matches `event_mask`.
+fn take_pending_sdl_event(event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+    PENDING_SDL_EVENT.with(|pending| pending.take())
+        .filter(|&(flags, _)| flags.intersects(event_mask))
+}
+
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    WheelUp,
+    WheelDown,
+}
+
+impl MouseButton {
+    /// Returns the buttons pressed (or the wheel movement) in a mouse event.
+    pub fn pressed(mouse: &Mouse) -> Vec<MouseButton> {
+        let states = [(mouse.lbutton_pressed, MouseButton::Left),
+                      (mouse.mbutton_pressed, MouseButton::Middle),
+                      (mouse.rbutton_pressed, MouseButton::Right),
+                      (mouse.wheel_up, MouseButton::WheelUp),
+                      (mouse.wheel_down, MouseButton::WheelDown)];
+        states.iter().filter(|&&(pressed, _)| pressed).map(|&(_, button)| button).collect()
+    }
+
+    /// Returns whether the button is held down in a mouse state. Always false for the wheel.
+    pub fn is_down(self, mouse: &Mouse) -> bool {
+        match self {
+            MouseButton::Left => mouse.lbutton,
+            MouseButton::Middle => mouse.mbutton,
+            MouseButton::Right => mouse.rbutton,
+            MouseButton::WheelUp | MouseButton::WheelDown => false,
+        }
+    }
+}
+
+/// Returns whether a key is currently held down.
+///
+/// The key status is updated when libtcod processes events, so `check_for_event` (or `events`)
+/// must be called every frame for this to be accurate. Keys producing printable characters are
+/// all reported as `KeyCode::Char`, use an [InputState](./struct.InputState.html) to tell them
+/// apart.
+pub fn is_key_down(code: KeyCode) -> bool {
+    unsafe {
+        let code = mem::transmute::<u32, ffi::TCOD_keycode_t>(code as u32);
+        ffi::TCOD_console_is_key_pressed(code) != 0
+    }
+}
+
+/// Returns the latest state of the mouse.
+///
+/// Like `is_key_down`, this is only updated when libtcod processes events.
+pub fn mouse_state() -> Mouse {
+    unsafe {
+        ffi::TCOD_mouse_get_status().into()
+    }
+}
 
 pub fn show_cursor(visible: bool) {
     unsafe {
@@ -169,7 +598,20 @@ pub fn move_cursor(x: i32, y: i32) {
     }
 }
 
+/// Sets whether touches also produce mouse events (as if the left button was used). This is on
+/// by default.
+///
+/// Turning it off leaves only the `FINGER_*` events, which keep their positions: a game that
+/// handles them itself (e.g. with [TouchGestures](../touch_gestures/struct.TouchGestures.html))
+/// can play without an emulated mouse.
+pub fn mouse_includes_touch(enable: bool) {
+    unsafe {
+        ffi::TCOD_mouse_includes_touch(enable as c_bool);
+    }
+}
+
 bitflags! {
+    #[derive(Debug)]
     flags KeyPressFlags: c_uint {
         const KEY_PRESSED  = ffi::TCOD_key_status_t::TCOD_KEY_PRESSED as c_uint,
         const KEY_RELEASED = ffi::TCOD_key_status_t::TCOD_KEY_RELEASED as c_uint,
@@ -177,6 +619,7 @@ bitflags! {
 }
 
 bitflags! {
+    #[derive(Debug)]
     flags EventFlags: c_uint {
         const KEY_PRESS     = ffi::TCOD_event_t::TCOD_EVENT_KEY_PRESS as c_uint,
         const KEY_RELEASE   = ffi::TCOD_event_t::TCOD_EVENT_KEY_RELEASE as c_uint,
@@ -185,11 +628,55 @@ bitflags! {
         const MOUSE_PRESS   = ffi::TCOD_event_t::TCOD_EVENT_MOUSE_PRESS as c_uint,
         const MOUSE_RELEASE = ffi::TCOD_event_t::TCOD_EVENT_MOUSE_RELEASE as c_uint,
         const MOUSE         = ffi::TCOD_event_t::TCOD_EVENT_MOUSE as c_uint,
+        const FINGER_MOVE   = ffi::TCOD_event_t::TCOD_EVENT_FINGER_MOVE as c_uint,
+        const FINGER_PRESS  = ffi::TCOD_event_t::TCOD_EVENT_FINGER_PRESS as c_uint,
+        const FINGER_RELEASE = ffi::TCOD_event_t::TCOD_EVENT_FINGER_RELEASE as c_uint,
+        /// Touch events, only reported with the `MOUSE_*` events in the mask as well (see
+        /// [Touch](./struct.Touch.html))
+        const FINGER        = ffi::TCOD_event_t::TCOD_EVENT_FINGER as c_uint,
         const ANY           = ffi::TCOD_event_t::TCOD_EVENT_ANY as c_uint,
     }
 }
 
+/// Returns the next event matching `event_mask`, or `None` if there is none.
+///
+/// The event comes from the current [InputSource](./trait.InputSource.html), which is SDL unless
+/// `set_input_source` was called on this thread.
 pub fn check_for_event(event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+    with_input_source(|source| source.check_for_event(event_mask))
+}
+
+/// Waits for an event matching `event_mask` and returns it.
+///
+/// With a `timeout`, `None` is returned if no event arrives in time. Without one, this blocks
+/// until an event arrives or the window is closed. Unlike calling `check_for_event` in a loop,
+/// the thread sleeps while waiting, so turn-based games don't have to burn CPU time between
+/// the player's actions.
+///
+/// libtcod can only wait without a timeout, so with one, `SdlInput` checks for events every
+/// 5 ms and sleeps in between: an event may be returned up to 5 ms after it arrived, and the
+/// thread wakes up about 200 times a second while waiting.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use tcod::input::{self, KEY_PRESS, MOUSE_PRESS};
+///
+/// match input::wait_for_event(KEY_PRESS | MOUSE_PRESS, Some(Duration::from_millis(500))) {
+///     Some((_flags, _event)) => { /* handle the input */ },
+///     None => { /* animate something and wait again */ },
+/// }
+/// ```
+pub fn wait_for_event(event_mask: EventFlags,
+                      timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+    with_input_source(|source| source.wait_for_event(event_mask, timeout))
+}
+
+fn sdl_check_for_event(event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+    if let Some(event) = take_pending_sdl_event(event_mask) {
+        return Some(event);
+    }
     let mut c_key_state: mem::MaybeUninit<ffi::TCOD_key_t> = mem::MaybeUninit::uninit();
     let mut c_mouse_state: mem::MaybeUninit<ffi::TCOD_mouse_t> = mem::MaybeUninit::uninit();
 
@@ -200,58 +687,308 @@ pub fn check_for_event(event_mask: EventFlags) -> Option<(EventFlags, Event)> {
 
     let c_key_state = unsafe { c_key_state.assume_init() };
     let c_mouse_state = unsafe { c_mouse_state.assume_init() };
+    event_from_native(event, c_key_state, c_mouse_state, native_touch_finger)
+}
 
-    let ret_flag = match event {
-        ffi::TCOD_event_t::TCOD_EVENT_KEY_PRESS => KEY_PRESS,
-        ffi::TCOD_event_t::TCOD_EVENT_KEY_RELEASE => KEY_RELEASE,
-        ffi::TCOD_event_t::TCOD_EVENT_KEY => KEY,
-        ffi::TCOD_event_t::TCOD_EVENT_MOUSE => MOUSE,
-        ffi::TCOD_event_t::TCOD_EVENT_MOUSE_MOVE => MOUSE_MOVE,
-        ffi::TCOD_event_t::TCOD_EVENT_MOUSE_PRESS => MOUSE_PRESS,
-        ffi::TCOD_event_t::TCOD_EVENT_MOUSE_RELEASE => MOUSE_RELEASE,
-        _ => ANY
+fn sdl_wait_for_event(event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+    if let Some(event) = take_pending_sdl_event(event_mask) {
+        return Some(event);
+    }
+    let mut c_key_state: mem::MaybeUninit<ffi::TCOD_key_t> = mem::MaybeUninit::uninit();
+    let mut c_mouse_state: mem::MaybeUninit<ffi::TCOD_mouse_t> = mem::MaybeUninit::uninit();
+
+    let event = unsafe {
+        ffi::TCOD_sys_wait_for_event(event_mask.bits() as i32,
+                                     c_key_state.as_mut_ptr(), c_mouse_state.as_mut_ptr(), 0)
     };
 
-    if ret_flag == ANY {
+    let c_key_state = unsafe { c_key_state.assume_init() };
+    let c_mouse_state = unsafe { c_mouse_state.assume_init() };
+    event_from_native(event, c_key_state, c_mouse_state, native_touch_finger)
+}
+
+fn event_from_native(event: c_int, c_key_state: ffi::TCOD_key_t, c_mouse_state: ffi::TCOD_mouse_t,
+                     touch_finger: fn() -> (usize, TouchPosition))
+                     -> Option<(EventFlags, Event)> {
+    let ret_flag = EventFlags::from_bits_truncate(event as c_uint);
+
+    let ret_event = if ret_flag.intersects(KEY) {
+        Event::Key(c_key_state.into())
+    } else if ret_flag.intersects(FINGER) {
+        let touch = Touch::from_native(ret_flag, touch_finger());
+        if ret_flag.intersects(MOUSE) {
+            // libtcod reports a finger and the mouse event emulated for it at once: return the
+            // touch now and the mouse event on the next call
+            let mouse = (ret_flag & MOUSE, Event::Mouse(c_mouse_state.into()));
+            PENDING_SDL_EVENT.with(|pending| pending.set(Some(mouse)));
+        }
+        return Some((ret_flag & FINGER, Event::Touch(touch)));
+    } else if ret_flag.intersects(MOUSE) {
+        Event::Mouse(c_mouse_state.into())
+    } else {
         return None
+    };
+
+    Some((ret_flag, ret_event))
+}
+
+/// A source of keyboard, mouse and touch events
+///
+/// `check_for_event`, `events` and the keypress methods of `Root` read their events from the
+/// current input source. By default that's SDL ([SdlInput](./struct.SdlInput.html)), but any
+/// other source (such as [ScriptedInput](./struct.ScriptedInput.html) in tests) can be installed
+/// with [set_input_source](./fn.set_input_source.html).
+pub trait InputSource {
+    /// Returns the next event matching `event_mask`. Events that don't match are discarded.
+    fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)>;
+
+    /// Waits up to `timeout` (forever if `None`) for an event matching `event_mask`.
+    ///
+    /// Sources that can't block return the next matching event or `None` immediately.
+    fn wait_for_event(&mut self, event_mask: EventFlags,
+                      timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+        let _ = timeout;
+        self.check_for_event(event_mask)
     }
 
-    let ret_event = if ret_flag.intersects(KEY_PRESS|KEY_RELEASE|KEY) {
-        Some(Event::Key(c_key_state.into()))
-    } else if ret_flag.intersects(MOUSE_MOVE|MOUSE_PRESS|MOUSE_RELEASE|MOUSE) {
-        Some(Event::Mouse(Mouse {
-            x: c_mouse_state.x as isize,
-            y: c_mouse_state.y as isize,
-            dx: c_mouse_state.dx as isize,
-            dy: c_mouse_state.dy as isize,
-            cx: c_mouse_state.cx as isize,
-            cy: c_mouse_state.cy as isize,
-            dcx: c_mouse_state.dcx as isize,
-            dcy: c_mouse_state.dcy as isize,
-            lbutton: c_mouse_state.lbutton != 0,
-            rbutton: c_mouse_state.rbutton != 0,
-            mbutton: c_mouse_state.mbutton != 0,
-            lbutton_pressed: c_mouse_state.lbutton_pressed != 0,
-            rbutton_pressed: c_mouse_state.rbutton_pressed != 0,
-            mbutton_pressed: c_mouse_state.mbutton_pressed != 0,
-            wheel_up: c_mouse_state.wheel_up != 0,
-            wheel_down: c_mouse_state.wheel_down != 0
-        }))
-    } else {
+    /// Returns the next key press.
+    ///
+    /// SDL blocks until a key is pressed. Sources that can't block return `Key::default()` (with
+    /// `KeyCode::NoKey`) when they run out of key presses.
+    fn wait_for_keypress(&mut self, flush: bool) -> Key {
+        let _ = flush;
+        loop {
+            match self.check_for_event(KEY_PRESS) {
+                Some((_, Event::Key(key))) => return key,
+                Some(_) => continue,
+                None => return Key::default(),
+            }
+        }
+    }
+
+    /// Returns the next key event with the given status, if there is one.
+    fn check_for_keypress(&mut self, status: KeyPressFlags) -> Option<Key> {
+        let mask = EventFlags::from_bits_truncate(status.bits()) & KEY;
+        match self.check_for_event(mask) {
+            Some((_, Event::Key(key))) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+/// The default input source, reading events from SDL through libtcod
+///
+/// `wait_for_event` with a timeout polls SDL every 5 ms, see
+/// [wait_for_event](./fn.wait_for_event.html).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SdlInput;
+
+impl InputSource for SdlInput {
+    fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        sdl_check_for_event(event_mask)
+    }
+
+    fn wait_for_event(&mut self, event_mask: EventFlags,
+                      timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return sdl_wait_for_event(event_mask),
+        };
+        // libtcod can only wait forever, so poll with short sleeps in between instead
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(event) = sdl_check_for_event(event_mask) {
+                return Some(event);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            thread::sleep(WAIT_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    fn wait_for_keypress(&mut self, flush: bool) -> Key {
+        let tcod_key = unsafe {
+            ffi::TCOD_console_wait_for_keypress(flush as c_bool)
+        };
+        tcod_key.into()
+    }
+
+    fn check_for_keypress(&mut self, status: KeyPressFlags) -> Option<Key> {
+        let tcod_key = unsafe {
+            ffi::TCOD_console_check_for_keypress(status.bits() as i32)
+        };
+        if tcod_key.vk == ffi::TCOD_keycode_t::TCODK_NONE {
+            return None;
+        }
+        Some(tcod_key.into())
+    }
+}
+
+/// An in-memory input source returning events pushed in advance
+///
+/// ```
+/// use tcod::input::{self, KeyCode, ScriptedInput};
+///
+/// let mut script = ScriptedInput::new();
+/// script.press_key(KeyCode::Down);
+/// script.press_char('q');
+/// input::set_input_source(script);
+///
+/// let keys: Vec<_> = input::events().collect();
+/// assert_eq!(keys.len(), 4); // a press and a release for both keys
+/// input::reset_input_source();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedInput {
+    events: VecDeque<(EventFlags, Event)>,
+}
+
+impl ScriptedInput {
+    pub fn new() -> Self {
+        ScriptedInput::default()
+    }
+
+    pub fn push_event(&mut self, flags: EventFlags, event: Event) {
+        self.events.push_back((flags, event));
+    }
+
+    /// Pushes a key event, which is a `KEY_PRESS` or `KEY_RELEASE` depending on `key.pressed`.
+    pub fn push_key(&mut self, key: Key) {
+        let flags = if key.pressed { KEY_PRESS } else { KEY_RELEASE };
+        self.push_event(flags, Event::Key(key));
+    }
+
+    /// Pushes a mouse event. It's a `MOUSE_RELEASE` if any of the `*_pressed` fields is set
+    /// (libtcod reports clicks when the button is released), a `MOUSE_PRESS` if a button is down
+    /// and a `MOUSE_MOVE` otherwise.
+    pub fn push_mouse(&mut self, mouse: Mouse) {
+        let flags = if mouse.lbutton_pressed || mouse.rbutton_pressed || mouse.mbutton_pressed {
+            MOUSE_RELEASE
+        } else if mouse.lbutton || mouse.rbutton || mouse.mbutton {
+            MOUSE_PRESS
+        } else {
+            MOUSE_MOVE
+        };
+        self.push_event(flags, Event::Mouse(mouse));
+    }
+
+    /// Pushes a touch event with the `FINGER_*` flag matching its phase.
+    pub fn push_touch(&mut self, touch: Touch) {
+        let flags = match touch.phase {
+            TouchPhase::Press => FINGER_PRESS,
+            TouchPhase::Move => FINGER_MOVE,
+            TouchPhase::Release => FINGER_RELEASE,
+        };
+        self.push_event(flags, Event::Touch(touch));
+    }
+
+    /// Pushes a press and a release of a key.
+    pub fn press_key(&mut self, code: KeyCode) {
+        let printable = match code {
+            KeyCode::Spacebar => ' ',
+            KeyCode::Enter | KeyCode::NumPadEnter => '\r',
+            KeyCode::Tab => '\t',
+            KeyCode::Escape => '\u{1b}',
+            KeyCode::Backspace => '\u{8}',
+            _ => '\0',
+        };
+        self.press(code, printable);
+    }
+
+    /// Pushes a press and a release of a key producing a printable character.
+    pub fn press_char(&mut self, printable: char) {
+        self.press(KeyCode::Char, printable);
+    }
+
+    fn press(&mut self, code: KeyCode, printable: char) {
+        let mut key = Key { code, printable, pressed: true, ..Default::default() };
+        self.push_key(key);
+        key.pressed = false;
+        self.push_key(key);
+    }
+
+    /// Pushes a left click on a console cell.
+    pub fn click(&mut self, cx: isize, cy: isize) {
+        let mouse = Mouse { cx, cy, lbutton: true, ..Default::default() };
+        self.push_mouse(mouse);
+        self.push_mouse(Mouse { lbutton: false, lbutton_pressed: true, ..mouse });
+    }
+
+    /// Returns the number of events that haven't been read yet.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        while let Some((flags, event)) = self.events.pop_front() {
+            if flags.intersects(event_mask) {
+                return Some((flags, event));
+            }
+        }
         None
-    };
+    }
+}
+
+thread_local! {
+    static INPUT_SOURCE: RefCell<Option<Box<dyn InputSource>>> = RefCell::new(None);
+}
 
-    ret_event.map(|event| (ret_flag, event))
+/// Makes `source` the input source of the current thread and returns the previous one (`None`
+/// when it was SDL).
+pub fn set_input_source<S: InputSource + 'static>(source: S) -> Option<Box<dyn InputSource>> {
+    INPUT_SOURCE.with(|current| current.borrow_mut().replace(Box::new(source)))
+}
+
+/// Goes back to reading events from SDL and returns the source that was used until now.
+pub fn reset_input_source() -> Option<Box<dyn InputSource>> {
+    INPUT_SOURCE.with(|current| current.borrow_mut().take())
+}
+
+pub(crate) fn with_input_source<T, F>(f: F) -> T where F: FnOnce(&mut dyn InputSource) -> T {
+    // The source is taken out while it's used so it can read events through the input functions
+    // itself (e.g. to record the events coming from SDL).
+    match INPUT_SOURCE.with(|current| current.borrow_mut().take()) {
+        Some(mut source) => {
+            let result = f(&mut *source);
+            INPUT_SOURCE.with(|current| {
+                let mut current = current.borrow_mut();
+                if current.is_none() {
+                    *current = Some(source);
+                }
+            });
+            result
+        }
+        None => f(&mut SdlInput),
+    }
 }
 
 pub fn events() -> EventIterator {
     EventIterator::new()
 }
 
+/// An input event
+///
+/// More kinds of events may be added, so a `match` on an `Event` needs a `_` arm.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub enum Event {
     Key(Key),
-    Mouse(Mouse)
+    Mouse(Mouse),
+    /// Only returned when `FINGER` events are requested, e.g. with `KEY | MOUSE | FINGER` or
+    /// `ANY`. When a touch is also passed on to the mouse, the touch (with the `FINGER_*` flags)
+    /// is followed by an `Event::Mouse` (with the `MOUSE_*` flags).
+    Touch(Touch),
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serialization", serde(skip))]
+    __Nonexhaustive,
 }
 
 pub struct EventIterator;
@@ -269,3 +1006,438 @@ impl Iterator for EventIterator {
         check_for_event(KEY | MOUSE)
     }
 }
+
+#[derive(Clone, Debug)]
+struct PressTracker<T: Eq + Hash> {
+    down: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+}
+
+impl<T: Copy + Eq + Hash> PressTracker<T> {
+    fn new() -> Self {
+        PressTracker { down: HashSet::new(), pressed: HashSet::new(), released: HashSet::new() }
+    }
+
+    fn press(&mut self, item: T) {
+        // Auto-repeated presses of a held key don't count as new presses
+        if self.down.insert(item) {
+            self.pressed.insert(item);
+        }
+    }
+
+    fn release(&mut self, item: T) {
+        if self.down.remove(&item) {
+            self.released.insert(item);
+        }
+    }
+
+    fn begin_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    fn clear(&mut self) {
+        self.down.clear();
+        self.begin_frame();
+    }
+}
+
+/// The keys and mouse buttons held down, pressed and released during a frame
+///
+/// Keys are tracked both by their `KeyCode` and, for the keys producing a printable character,
+/// by the character (all such keys have `KeyCode::Char`).
+///
+/// ```no_run
+/// use tcod::input::{InputState, KeyCode, MouseButton};
+///
+/// let mut input = InputState::new();
+/// loop {
+///     for (_flags, _event) in input.update() {
+///         // the events are still available for menus, text input, ...
+///     }
+///     if input.is_key_down(KeyCode::Left) {
+///         // move continuously while the key is held
+///     }
+///     if input.is_button_pressed(MouseButton::Left) {
+///         // start a drag selection at input.mouse().cx, input.mouse().cy
+///     }
+///     if input.is_char_pressed('q') {
+///         break;
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct InputState {
+    keys: PressTracker<KeyCode>,
+    chars: PressTracker<char>,
+    buttons: PressTracker<MouseButton>,
+    mouse: Mouse,
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        InputState::new()
+    }
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        InputState {
+            keys: PressTracker::new(),
+            chars: PressTracker::new(),
+            buttons: PressTracker::new(),
+            mouse: Mouse::default(),
+        }
+    }
+
+    /// Starts a new frame and processes all the pending key and mouse events. The events are
+    /// returned so they can be handled by the rest of the game too.
+    pub fn update(&mut self) -> Vec<(EventFlags, Event)> {
+        self.begin_frame();
+        let events: Vec<_> = events().collect();
+        for &(flags, ref event) in &events {
+            self.handle_event(flags, event);
+        }
+        events
+    }
+
+    /// Forgets what was pressed and released in the previous frame. `update` calls this, it's
+    /// only needed when the events are passed to `handle_event` manually.
+    pub fn begin_frame(&mut self) {
+        self.keys.begin_frame();
+        self.chars.begin_frame();
+        self.buttons.begin_frame();
+    }
+
+    /// Updates the state with an event of the current frame.
+    pub fn handle_event(&mut self, flags: EventFlags, event: &Event) {
+        match *event {
+            // Text input doesn't correspond to a physical key
+            Event::Key(ref key) if key.code == KeyCode::Text => {}
+            Event::Key(ref key) => {
+                let pressed = if flags.intersects(KEY_PRESS | KEY_RELEASE) {
+                    flags.contains(KEY_PRESS)
+                } else {
+                    key.pressed
+                };
+                if pressed {
+                    self.keys.press(key.code);
+                } else {
+                    self.keys.release(key.code);
+                }
+                if key.printable != '\0' {
+                    if pressed {
+                        self.chars.press(key.printable);
+                    } else {
+                        self.chars.release(key.printable);
+                    }
+                }
+            }
+            Event::Mouse(ref mouse) => {
+                for &(clicked, button) in &[(mouse.lbutton_pressed, MouseButton::Left),
+                                            (mouse.mbutton_pressed, MouseButton::Middle),
+                                            (mouse.rbutton_pressed, MouseButton::Right)] {
+                    if button.is_down(mouse) {
+                        self.buttons.press(button);
+                    } else {
+                        // libtcod merges the events of one poll, so a quick click may only show
+                        // up as `*_pressed`: it's then pressed and released at once
+                        if clicked {
+                            self.buttons.press(button);
+                        }
+                        self.buttons.release(button);
+                    }
+                }
+                // The wheel can't be held, so it's pressed and released at once
+                for &(moved, button) in &[(mouse.wheel_up, MouseButton::WheelUp),
+                                          (mouse.wheel_down, MouseButton::WheelDown)] {
+                    if moved {
+                        self.buttons.press(button);
+                        self.buttons.release(button);
+                    }
+                }
+                self.mouse = *mouse;
+            }
+            // Touches the mouse should see arrive as separate mouse events
+            Event::Touch(_) | Event::__Nonexhaustive => {}
+        }
+    }
+
+    /// Forgets all the held keys and buttons, e.g. after the window lost focus.
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.chars.clear();
+        self.buttons.clear();
+    }
+
+    /// Returns whether the key is held down.
+    pub fn is_key_down(&self, code: KeyCode) -> bool {
+        self.keys.down.contains(&code)
+    }
+
+    /// Returns whether the key was pressed during the current frame.
+    pub fn is_key_pressed(&self, code: KeyCode) -> bool {
+        self.keys.pressed.contains(&code)
+    }
+
+    /// Returns whether the key was released during the current frame.
+    pub fn is_key_released(&self, code: KeyCode) -> bool {
+        self.keys.released.contains(&code)
+    }
+
+    /// Returns whether the key producing `printable` is held down.
+    pub fn is_char_down(&self, printable: char) -> bool {
+        self.chars.down.contains(&printable)
+    }
+
+    /// Returns whether the key producing `printable` was pressed during the current frame.
+    pub fn is_char_pressed(&self, printable: char) -> bool {
+        self.chars.pressed.contains(&printable)
+    }
+
+    /// Returns whether the key producing `printable` was released during the current frame.
+    pub fn is_char_released(&self, printable: char) -> bool {
+        self.chars.released.contains(&printable)
+    }
+
+    /// Returns whether the mouse button is held down.
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        self.buttons.down.contains(&button)
+    }
+
+    /// Returns whether the mouse button was pressed (or the wheel moved) during the current frame.
+    pub fn is_button_pressed(&self, button: MouseButton) -> bool {
+        self.buttons.pressed.contains(&button)
+    }
+
+    /// Returns whether the mouse button was released during the current frame.
+    pub fn is_button_released(&self, button: MouseButton) -> bool {
+        self.buttons.released.contains(&button)
+    }
+
+    /// Returns the latest mouse state.
+    pub fn mouse(&self) -> Mouse {
+        self.mouse
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem;
+    use std::time::Duration;
+    use bindings::ffi;
+    use super::{InputSource, InputState, Key, KeyChord, KeyCode, Mouse, MouseButton, ScriptedInput, Event,
+                KEY, KEY_PRESS, KEY_RELEASE, KEY_PRESSED, KEY_RELEASED, MOUSE, MOUSE_PRESS,
+                MOUSE_RELEASE};
+    use super::{event_from_native, take_pending_sdl_event, TouchPhase, TouchPosition, ANY, FINGER,
+                FINGER_PRESS, FINGER_RELEASE};
+
+    #[test]
+    fn touches_from_native() {
+        fn first_finger() -> (usize, TouchPosition) {
+            (0, TouchPosition { x: 40, y: 64, cx: 5, cy: 8, ..Default::default() })
+        }
+        fn second_finger() -> (usize, TouchPosition) {
+            (1, TouchPosition { x: 96, y: 16, cx: 12, cy: 2, ..Default::default() })
+        }
+
+        let key: ffi::TCOD_key_t = unsafe { mem::zeroed() };
+        let mut mouse: ffi::TCOD_mouse_t = unsafe { mem::zeroed() };
+        mouse.x = 40;
+        mouse.y = 64;
+        mouse.cx = 5;
+        mouse.cy = 8;
+        mouse.lbutton = true as ::bindings::c_bool;
+
+        // A finger passed on to the mouse: the touch comes first, then the mouse event
+        let flags = (FINGER_PRESS | MOUSE_PRESS).bits() as i32;
+        match event_from_native(flags, key, mouse, first_finger) {
+            Some((FINGER_PRESS, Event::Touch(touch))) => {
+                assert_eq!(touch.phase, TouchPhase::Press);
+                assert_eq!(touch.finger, 0);
+                assert_eq!((touch.position.cx, touch.position.cy), (5, 8));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match take_pending_sdl_event(ANY) {
+            Some((MOUSE_PRESS, Event::Mouse(m))) => assert!(m.lbutton && m.cx == 5),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(take_pending_sdl_event(ANY).is_none());
+
+        // A second finger isn't passed on to the mouse, but still has its position
+        match event_from_native(FINGER_PRESS.bits() as i32, key, mouse, second_finger) {
+            Some((flags, Event::Touch(touch))) => {
+                assert!(flags.intersects(FINGER));
+                assert_eq!(touch.finger, 1);
+                assert_eq!((touch.position.cx, touch.position.cy), (12, 2));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match event_from_native(FINGER_RELEASE.bits() as i32, key, mouse, second_finger) {
+            Some((FINGER_RELEASE, Event::Touch(touch))) => {
+                assert_eq!(touch.phase, TouchPhase::Release);
+                assert_eq!(touch.finger, 1);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(take_pending_sdl_event(ANY).is_none());
+    }
+
+    #[test]
+    fn key_to_native() {
+        let mut key = Key { code: KeyCode::Char, printable: 'x', pressed: true, ..Default::default() };
+        key.left_ctrl = true;
+        key.ctrl = true;
+        key.set_text("x");
+        assert_eq!(Key::from(::bindings::ffi::TCOD_key_t::from(key)), key);
+    }
+
+    #[test]
+    fn scripted_input() {
+        let mut script = ScriptedInput::new();
+        script.click(3, 4);
+        script.press_char('a');
+        assert_eq!(script.len(), 4);
+
+        match script.check_for_event(MOUSE) {
+            Some((MOUSE_PRESS, Event::Mouse(Mouse { cx: 3, cy: 4, lbutton: true, .. }))) => {}
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match script.check_for_event(KEY | MOUSE) {
+            Some((MOUSE_RELEASE, Event::Mouse(mouse))) => assert!(mouse.lbutton_pressed),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match script.check_for_event(KEY) {
+            Some((KEY_PRESS, Event::Key(key))) => assert_eq!(key.printable, 'a'),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(script.check_for_keypress(KEY_PRESSED).is_none());
+        assert!(script.is_empty());
+    }
+
+    #[test]
+    fn scripted_keypresses() {
+        let mut script = ScriptedInput::new();
+        script.push_mouse(Mouse::default());
+        script.press_key(KeyCode::Enter);
+        script.press_key(KeyCode::Up);
+        assert_eq!(script.wait_for_keypress(true).code, KeyCode::Enter);
+        assert_eq!(script.check_for_keypress(KEY_RELEASED).map(|k| k.code), Some(KeyCode::Enter));
+        assert_eq!(script.wait_for_keypress(false).code, KeyCode::Up);
+        assert_eq!(script.wait_for_keypress(false), Key::default());
+    }
+
+    #[test]
+    fn scripted_wait_for_event() {
+        let mut script = ScriptedInput::new();
+        script.press_key(KeyCode::Up);
+        assert!(script.wait_for_event(MOUSE, Some(Duration::from_millis(10))).is_none());
+        assert!(script.is_empty());
+    }
+
+    #[test]
+    fn key_code_names() {
+        assert_eq!(KeyCode::NumPad8.to_string(), "NumPad8");
+        assert_eq!("pageup".parse(), Ok(KeyCode::PageUp));
+        assert!("Foo".parse::<KeyCode>().is_err());
+    }
+
+    #[test]
+    fn key_chords() {
+        assert_eq!("Ctrl+Shift+F5".parse(), Ok(KeyChord::new(KeyCode::F5).ctrl().shift()));
+        assert_eq!("Ctrl++".parse(), Ok(KeyChord::char('+').ctrl()));
+        assert_eq!("+".parse(), Ok(KeyChord::char('+')));
+        assert!("Ctrl+".parse::<KeyChord>().is_err());
+        assert!("Hyper+x".parse::<KeyChord>().is_err());
+        for text in &["Alt+x", "Ctrl+Alt+Shift+Escape", "Shift+Char", "Ctrl++"] {
+            assert_eq!(text.parse::<KeyChord>().unwrap().to_string(), *text);
+        }
+
+        let mut key = Key { code: KeyCode::Char, printable: 'x', pressed: true, ..Default::default() };
+        key.alt = true;
+        key.left_alt = true;
+        assert!(KeyChord::char('x').alt().matches(&key));
+        assert!(!KeyChord::char('x').matches(&key));
+    }
+
+    #[test]
+    fn input_state() {
+        let mut state = InputState::new();
+        let mut up = Key { code: KeyCode::Up, pressed: true, ..Default::default() };
+        state.handle_event(KEY_PRESS, &Event::Key(up));
+        state.handle_event(MOUSE_PRESS, &Event::Mouse(Mouse { lbutton: true, ..Default::default() }));
+        assert!(state.is_key_down(KeyCode::Up) && state.is_key_pressed(KeyCode::Up));
+        assert!(state.is_button_down(MouseButton::Left) && state.is_button_pressed(MouseButton::Left));
+
+        // An auto-repeated press isn't a new press
+        state.begin_frame();
+        state.handle_event(KEY_PRESS, &Event::Key(up));
+        assert!(state.is_key_down(KeyCode::Up) && !state.is_key_pressed(KeyCode::Up));
+
+        state.begin_frame();
+        up.pressed = false;
+        state.handle_event(KEY_RELEASE, &Event::Key(up));
+        let a = Key { code: KeyCode::Char, printable: 'a', pressed: true, ..Default::default() };
+        state.handle_event(KEY_PRESS, &Event::Key(a));
+        state.handle_event(MOUSE_RELEASE, &Event::Mouse(Mouse { lbutton_pressed: true, wheel_up: true,
+                                                                ..Default::default() }));
+        assert!(!state.is_key_down(KeyCode::Up) && state.is_key_released(KeyCode::Up));
+        assert!(state.is_char_pressed('a') && state.is_key_pressed(KeyCode::Char));
+        assert!(state.is_button_released(MouseButton::Left));
+        assert!(state.is_button_pressed(MouseButton::WheelUp) && !state.is_button_down(MouseButton::WheelUp));
+        // The press was in an earlier frame
+        assert!(!state.is_button_pressed(MouseButton::Left));
+
+        // A click that started and ended within one poll
+        state.begin_frame();
+        let click = Mouse { rbutton_pressed: true, ..Default::default() };
+        state.handle_event(MOUSE_RELEASE, &Event::Mouse(click));
+        assert!(state.is_button_pressed(MouseButton::Right));
+        assert!(state.is_button_released(MouseButton::Right));
+        assert!(!state.is_button_down(MouseButton::Right));
+    }
+
+    #[test]
+    fn thread_input_source() {
+        let mut script = ScriptedInput::new();
+        script.press_key(KeyCode::Escape);
+        assert!(super::set_input_source(script).is_none());
+        assert_eq!(super::events().count(), 2);
+        assert!(super::reset_input_source().is_some());
+    }
+}
+
+#[cfg(all(feature = "serialization", test))]
+mod test_serialization {
+    use super::{Event, Key, KeyChord, KeyCode, Mouse};
+    use serde_json;
+
+    #[test]
+    fn key_round_trip() {
+        let mut key = Key { code: KeyCode::Text, pressed: true, ..Default::default() };
+        key.set_text("ž");
+        let encoded = serde_json::to_string(&Event::Key(key)).unwrap();
+        assert!(encoded.contains("\"text\":\"ž\""));
+        match serde_json::from_str(&encoded).unwrap() {
+            Event::Key(decoded) => assert_eq!(decoded, key),
+            _ => panic!("expected a key event"),
+        }
+    }
+
+    #[test]
+    fn mouse_round_trip() {
+        let mouse = Mouse { cx: 4, cy: 2, lbutton: true, ..Default::default() };
+        let encoded = serde_json::to_string(&mouse).unwrap();
+        assert_eq!(serde_json::from_str::<Mouse>(&encoded).unwrap(), mouse);
+    }
+
+    #[test]
+    fn key_chord_as_string() {
+        let chord = KeyChord::new(KeyCode::F5).ctrl();
+        assert_eq!(serde_json::to_string(&chord).unwrap(), "\"Ctrl+F5\"");
+        assert_eq!(serde_json::from_str::<KeyChord>("\"Alt+x\"").unwrap(), KeyChord::char('x').alt());
+        assert_eq!(serde_json::to_string(&KeyCode::Escape).unwrap(), "\"Escape\"");
+    }
+}
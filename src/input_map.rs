@@ -0,0 +1,596 @@
+//! Mapping keys and mouse buttons to game actions
+//!
+//! An [InputMap](./struct.InputMap.html) binds keys, printable characters and mouse buttons
+//! (optionally combined with the Ctrl, Alt and Shift modifiers) to actions defined by the game,
+//! so the input handling doesn't have to match on the `Key` fields directly:
+//!
+//! ```no_run
+//! use tcod::input::{self, KeyCode};
+//! use tcod::input_map::{Binding, InputMap};
+//!
+//! #[derive(Clone, PartialEq, Debug)]
+//! enum Action { MoveUp, MoveDown, Quit }
+//!
+//! let mut bindings = InputMap::new();
+//! bindings.bind(Action::MoveUp, Binding::key(KeyCode::Up)).unwrap();
+//! bindings.bind(Action::MoveUp, Binding::char('k')).unwrap();
+//! bindings.bind(Action::MoveDown, Binding::key(KeyCode::Down)).unwrap();
+//! bindings.bind(Action::Quit, Binding::char('q').ctrl()).unwrap();
+//!
+//! for (_, event) in input::events() {
+//!     match bindings.action(&event) {
+//!         Some(&Action::MoveUp) => {},
+//!         Some(&Action::MoveDown) => {},
+//!         Some(&Action::Quit) => break,
+//!         None => {},
+//!     }
+//! }
+//! ```
+//!
+//! The bindings can be stored in a plain text file. Every line binds an action to a
+//! comma-separated list of bindings and `#` starts a comment:
+//!
+//! ```text
+//! # Movement
+//! MoveUp = Up, NumPad8, 'k'
+//! Quit = Ctrl+'q', Escape
+//! Select = MouseLeft
+//! ```
+//!
+//! Key names are the `KeyCode` variant names, printable characters are written in single quotes
+//! (a single unquoted character works too, as in `KeyChord`)
+//! and the mouse buttons are `MouseLeft`, `MouseMiddle`, `MouseRight`, `WheelUp` and
+//! `WheelDown`. The actions are read and written using their `FromStr` and `Display`
+//! implementations.
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use input::{Event, Key, KeyChord, KeyCode, Mouse};
+pub use input::MouseButton;
+
+static MOUSE_BUTTON_NAMES: [(MouseButton, &str); 5] = [
+    (MouseButton::Left, "MouseLeft"), (MouseButton::Middle, "MouseMiddle"),
+    (MouseButton::Right, "MouseRight"), (MouseButton::WheelUp, "WheelUp"),
+    (MouseButton::WheelDown, "WheelDown"),
+];
+
+/// What has to be pressed to trigger a binding
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Trigger {
+    /// A key identified by its `KeyCode`
+    Key(KeyCode),
+    /// A key producing the given `Key::printable` character
+    Char(char),
+    /// A mouse button or a wheel movement
+    Mouse(MouseButton),
+}
+
+/// The modifier keys that must be held down for a binding to match
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl Modifiers {
+    /// Returns the modifiers held down during a key event.
+    pub fn of_key(key: &Key) -> Modifiers {
+        Modifiers { ctrl: key.ctrl, alt: key.alt, shift: key.shift }
+    }
+}
+
+/// A trigger combined with modifier keys
+///
+/// A binding matches a key event only when exactly the same modifiers are held down. The
+/// exception are characters typed with Shift: `'#'` matches the text event libtcod sends for
+/// Shift+3 on a US layout, whether Shift is part of the binding or not. Mouse bindings don't
+/// support modifiers.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Binding {
+    pub trigger: Trigger,
+    pub modifiers: Modifiers,
+}
+
+impl Binding {
+    pub fn key(code: KeyCode) -> Binding {
+        Binding { trigger: Trigger::Key(code), modifiers: Modifiers::default() }
+    }
+
+    pub fn char(printable: char) -> Binding {
+        Binding { trigger: Trigger::Char(printable), modifiers: Modifiers::default() }
+    }
+
+    pub fn mouse(button: MouseButton) -> Binding {
+        Binding { trigger: Trigger::Mouse(button), modifiers: Modifiers::default() }
+    }
+
+    pub fn ctrl(mut self) -> Binding {
+        self.modifiers.ctrl = true;
+        self
+    }
+
+    pub fn alt(mut self) -> Binding {
+        self.modifiers.alt = true;
+        self
+    }
+
+    pub fn shift(mut self) -> Binding {
+        self.modifiers.shift = true;
+        self
+    }
+
+    /// Returns whether the binding matches a pressed key. Released keys never match.
+    pub fn matches_key(&self, key: &Key) -> bool {
+        if !key.pressed {
+            return false;
+        }
+        match self.trigger {
+            Trigger::Key(code) => key.code == code && self.modifiers == Modifiers::of_key(key),
+            Trigger::Char(printable) if key.code == KeyCode::Text => {
+                // libtcod reports a shifted character as the unshifted key with `shift` set,
+                // followed by a text event with the character. The text event of a key that
+                // already matched on its own (e.g. 'k' then "k") is skipped.
+                let mut chars = key.text().chars();
+                chars.next() == Some(printable) && chars.next().is_none() &&
+                    key.printable != printable &&
+                    self.modifiers.ctrl == key.ctrl && self.modifiers.alt == key.alt
+            }
+            Trigger::Char(printable) => {
+                key.printable == printable && self.modifiers == Modifiers::of_key(key)
+            }
+            Trigger::Mouse(_) => false,
+        }
+    }
+
+    /// Returns whether the binding matches a button pressed in a mouse event.
+    pub fn matches_mouse(&self, mouse: &Mouse) -> bool {
+        match self.trigger {
+            Trigger::Mouse(button) if self.modifiers == Modifiers::default() => {
+                MouseButton::pressed(mouse).contains(&button)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<KeyChord> for Binding {
+    fn from(chord: KeyChord) -> Binding {
+        let trigger = if chord.code == KeyCode::Char {
+            Trigger::Char(chord.printable)
+        } else {
+            Trigger::Key(chord.code)
+        };
+        let modifiers = Modifiers { ctrl: chord.ctrl, alt: chord.alt, shift: chord.shift };
+        Binding { trigger, modifiers }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        match self.trigger {
+            Trigger::Key(code) => write!(f, "{}", code),
+            Trigger::Char(printable) => write!(f, "'{}'", printable),
+            Trigger::Mouse(button) => {
+                let &(_, name) = MOUSE_BUTTON_NAMES.iter().find(|&&(b, _)| b == button).unwrap();
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+/// An error returned when parsing bindings or an input map configuration fails
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseBindingError {
+    message: String,
+}
+
+impl ParseBindingError {
+    fn new<T: Into<String>>(message: T) -> ParseBindingError {
+        ParseBindingError { message: message.into() }
+    }
+}
+
+impl fmt::Display for ParseBindingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for ParseBindingError {}
+
+impl FromStr for Binding {
+    type Err = ParseBindingError;
+
+    fn from_str(s: &str) -> Result<Binding, ParseBindingError> {
+        let mut modifiers = Modifiers::default();
+        let mut rest = s.trim();
+        loop {
+            if let Some(r) = rest.strip_prefix("Ctrl+") {
+                modifiers.ctrl = true;
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("Alt+") {
+                modifiers.alt = true;
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("Shift+") {
+                modifiers.shift = true;
+                rest = r;
+            } else {
+                break;
+            }
+        }
+
+        let trigger = if rest.len() > 2 && rest.starts_with('\'') && rest.ends_with('\'') {
+            let mut chars = rest[1..rest.len() - 1].chars();
+            match (chars.next(), chars.next()) {
+                (Some(printable), None) => Trigger::Char(printable),
+                _ => return Err(ParseBindingError::new(format!("Invalid character: {}", rest))),
+            }
+        } else if let Some(&(button, _)) = MOUSE_BUTTON_NAMES.iter().find(|&&(_, name)| name == rest) {
+            if modifiers != Modifiers::default() {
+                return Err(ParseBindingError::new(
+                    format!("Mouse bindings can't have modifiers: {}", s.trim())));
+            }
+            Trigger::Mouse(button)
+        } else if let Ok(chord) = rest.parse::<KeyChord>() {
+            Binding::from(chord).trigger
+        } else {
+            return Err(ParseBindingError::new(format!("Unknown key or button: {}", rest)));
+        };
+        Ok(Binding { trigger, modifiers })
+    }
+}
+
+/// A binding that can't be added because it's already bound to a different action
+#[derive(Clone, PartialEq, Debug)]
+pub struct BindingConflict<A> {
+    pub binding: Binding,
+    pub existing: A,
+    pub rejected: A,
+}
+
+/// Bindings from keys and mouse buttons to actions
+///
+/// Every action can have any number of bindings, but a binding can only trigger a single action.
+#[derive(Clone, Debug)]
+pub struct InputMap<A> {
+    bindings: Vec<(Binding, A)>,
+}
+
+impl<A> Default for InputMap<A> {
+    fn default() -> Self {
+        InputMap { bindings: Vec::new() }
+    }
+}
+
+impl<A: PartialEq> InputMap<A> {
+    pub fn new() -> Self {
+        InputMap::default()
+    }
+
+    /// Binds `binding` to `action`.
+    ///
+    /// Fails without changing anything if the binding already triggers a different action.
+    pub fn bind(&mut self, action: A, binding: Binding) -> Result<(), BindingConflict<A>>
+        where A: Clone
+    {
+        match self.action_for(&binding) {
+            Some(existing) if *existing == action => Ok(()),
+            Some(existing) => Err(BindingConflict {
+                binding,
+                existing: existing.clone(),
+                rejected: action,
+            }),
+            None => {
+                self.bindings.push((binding, action));
+                Ok(())
+            }
+        }
+    }
+
+    /// Binds `binding` to `action`, replacing any existing binding. Returns the action that was
+    /// previously bound to it.
+    pub fn rebind(&mut self, action: A, binding: Binding) -> Option<A> {
+        let previous = self.unbind(&binding);
+        self.bindings.push((binding, action));
+        previous
+    }
+
+    /// Removes a binding and returns the action it was bound to.
+    pub fn unbind(&mut self, binding: &Binding) -> Option<A> {
+        self.bindings.iter()
+            .position(|(b, _)| b == binding)
+            .map(|index| self.bindings.remove(index).1)
+    }
+
+    /// Removes all the bindings of an action and returns them.
+    pub fn unbind_action(&mut self, action: &A) -> Vec<Binding> {
+        let removed = self.bindings(action);
+        self.bindings.retain(|(_, a)| a != action);
+        removed
+    }
+
+    /// Removes all the bindings.
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+    }
+
+    /// Returns the bindings of an action in the order they were added.
+    pub fn bindings(&self, action: &A) -> Vec<Binding> {
+        self.bindings.iter()
+            .filter(|(_, a)| a == action)
+            .map(|&(binding, _)| binding)
+            .collect()
+    }
+
+    /// Returns all the bindings and their actions in the order they were added.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, (Binding, A)> {
+        self.bindings.iter()
+    }
+
+    /// Returns the action bound to `binding`.
+    pub fn action_for(&self, binding: &Binding) -> Option<&A> {
+        self.bindings.iter().find(|(b, _)| b == binding).map(|(_, action)| action)
+    }
+
+    /// Returns the action triggered by a pressed key.
+    ///
+    /// A binding to the key's `KeyCode` takes precedence over one to its printable character.
+    pub fn key_action(&self, key: &Key) -> Option<&A> {
+        let matching = |by_code: bool| {
+            self.bindings.iter().find(|(binding, _)| {
+                let is_code = matches!(binding.trigger, Trigger::Key(_));
+                is_code == by_code && binding.matches_key(key)
+            })
+        };
+        matching(true).or_else(|| matching(false)).map(|(_, action)| action)
+    }
+
+    /// Returns the action triggered by the buttons pressed in a mouse event.
+    pub fn mouse_action(&self, mouse: &Mouse) -> Option<&A> {
+        self.bindings.iter()
+            .find(|(binding, _)| binding.matches_mouse(mouse))
+            .map(|(_, action)| action)
+    }
+
+    /// Returns the action triggered by an event.
+    pub fn action(&self, event: &Event) -> Option<&A> {
+        match *event {
+            Event::Key(ref key) => self.key_action(key),
+            Event::Mouse(ref mouse) => self.mouse_action(mouse),
+            Event::Touch(_) | Event::__Nonexhaustive => None,
+        }
+    }
+
+    /// Parses bindings in the configuration format described in the
+    /// [module documentation](./index.html).
+    ///
+    /// Conflicting bindings are reported as errors.
+    pub fn parse(config: &str) -> Result<InputMap<A>, ParseBindingError>
+        where A: FromStr + Clone + fmt::Display
+    {
+        let mut map = InputMap::new();
+        for (number, line) in config.lines().enumerate() {
+            let line = match find_unquoted(line, '#') {
+                Some(index) => &line[..index],
+                None => line,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let error = |message: String| {
+                ParseBindingError::new(format!("Line {}: {}", number + 1, message))
+            };
+            let separator = line.find('=').ok_or_else(|| error("Missing '='".to_owned()))?;
+            let action: A = line[..separator].trim().parse()
+                .map_err(|_| error(format!("Unknown action: {}", line[..separator].trim())))?;
+            for binding in split_bindings(&line[separator + 1..]) {
+                let binding = binding.parse().map_err(|e: ParseBindingError| error(e.message))?;
+                map.bind(action.clone(), binding).map_err(|conflict| {
+                    error(format!("{} is bound to both {} and {}",
+                                  conflict.binding, conflict.existing, conflict.rejected))
+                })?;
+            }
+        }
+        Ok(map)
+    }
+
+    /// Writes the bindings in the configuration format, one line per action.
+    pub fn to_config(&self) -> String where A: fmt::Display {
+        let mut actions: Vec<&A> = vec![];
+        for (_, action) in &self.bindings {
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+        let mut config = String::new();
+        for action in actions {
+            let bindings: Vec<String> = self.bindings(action).iter()
+                .map(|binding| binding.to_string())
+                .collect();
+            config.push_str(&format!("{} = {}\n", action, bindings.join(", ")));
+        }
+        config
+    }
+
+    /// Loads the bindings from a configuration file.
+    pub fn load<P>(path: P) -> Result<InputMap<A>, Error>
+        where P: AsRef<Path>, A: FromStr + Clone + fmt::Display
+    {
+        let mut config = String::new();
+        File::open(path)?.read_to_string(&mut config)?;
+        InputMap::parse(&config).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Saves the bindings to a configuration file.
+    pub fn save<P>(&self, path: P) -> Result<(), Error>
+        where P: AsRef<Path>, A: fmt::Display
+    {
+        File::create(path)?.write_all(self.to_config().as_bytes())
+    }
+}
+
+/// Returns the index of the first `target` that isn't a quoted character binding such as `','`.
+fn find_unquoted(text: &str, target: char) -> Option<usize> {
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if c == '\'' {
+            chars.next();
+            chars.next();
+        } else if c == target {
+            return Some(index);
+        }
+    }
+    None
+}
+
+/// Splits a comma-separated list of bindings.
+fn split_bindings(mut list: &str) -> Vec<&str> {
+    let mut bindings = vec![];
+    while let Some(index) = find_unquoted(list, ',') {
+        bindings.push(list[..index].trim());
+        list = &list[index + 1..];
+    }
+    bindings.push(list.trim());
+    bindings.into_iter().filter(|b| !b.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Binding, InputMap, MouseButton, split_bindings};
+    use input::{Event, Key, KeyCode, Mouse};
+
+    #[derive(Copy, Clone, PartialEq, Debug)]
+    enum Action { Up, Quit, Select }
+
+    impl ::std::str::FromStr for Action {
+        type Err = ();
+
+        fn from_str(s: &str) -> Result<Action, ()> {
+            match s {
+                "Up" => Ok(Action::Up),
+                "Quit" => Ok(Action::Quit),
+                "Select" => Ok(Action::Select),
+                _ => Err(()),
+            }
+        }
+    }
+
+    impl ::std::fmt::Display for Action {
+        fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+            write!(f, "{:?}", self)
+        }
+    }
+
+    fn pressed(code: KeyCode, printable: char) -> Key {
+        let mut key = Key::default();
+        key.code = code;
+        key.printable = printable;
+        key.pressed = true;
+        key
+    }
+
+    #[test]
+    fn parse_bindings() {
+        assert_eq!("Up".parse(), Ok(Binding::key(KeyCode::Up)));
+        assert_eq!("Ctrl+Shift+F5".parse(), Ok(Binding::key(KeyCode::F5).ctrl().shift()));
+        assert_eq!("Alt+'+'".parse(), Ok(Binding::char('+').alt()));
+        assert_eq!("Ctrl+k".parse(), Ok(Binding::char('k').ctrl()));
+        assert_eq!("WheelDown".parse(), Ok(Binding::mouse(MouseButton::WheelDown)));
+        assert!("Ctrl+MouseLeft".parse::<Binding>().is_err());
+        assert!("Foo".parse::<Binding>().is_err());
+        assert!("'ab'".parse::<Binding>().is_err());
+        assert_eq!(Binding::char('q').ctrl().alt().to_string(), "Ctrl+Alt+'q'");
+    }
+
+    #[test]
+    fn split_binding_lists() {
+        assert_eq!(split_bindings(" Up, ',' ,''', Ctrl+'k'"), vec!["Up", "','", "'''", "Ctrl+'k'"]);
+        assert_eq!(split_bindings("','"), vec!["','"]);
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut map = InputMap::new();
+        assert!(map.bind(Action::Up, Binding::key(KeyCode::Up)).is_ok());
+        assert!(map.bind(Action::Up, Binding::key(KeyCode::Up)).is_ok());
+        let conflict = map.bind(Action::Quit, Binding::key(KeyCode::Up)).unwrap_err();
+        assert_eq!(conflict.existing, Action::Up);
+        assert_eq!(conflict.rejected, Action::Quit);
+        assert_eq!(map.rebind(Action::Quit, Binding::key(KeyCode::Up)), Some(Action::Up));
+        assert_eq!(map.bindings(&Action::Up), vec![]);
+    }
+
+    #[test]
+    fn matching_events() {
+        let mut map = InputMap::new();
+        map.bind(Action::Up, Binding::char('k')).unwrap();
+        map.bind(Action::Quit, Binding::char('q').ctrl()).unwrap();
+        map.bind(Action::Select, Binding::mouse(MouseButton::Left)).unwrap();
+
+        assert_eq!(map.key_action(&pressed(KeyCode::Char, 'k')), Some(&Action::Up));
+        assert_eq!(map.key_action(&pressed(KeyCode::Char, 'q')), None);
+        let mut ctrl_q = pressed(KeyCode::Char, 'q');
+        ctrl_q.ctrl = true;
+        ctrl_q.left_ctrl = true;
+        assert_eq!(map.key_action(&ctrl_q), Some(&Action::Quit));
+        let mut released = pressed(KeyCode::Char, 'k');
+        released.pressed = false;
+        assert_eq!(map.key_action(&released), None);
+
+        // libtcod sends Shift+3 then the text "#" for '#', and Alt+Shift+= then "+" for Alt+'+'
+        map.bind(Action::Select, Binding::char('#')).unwrap();
+        map.bind(Action::Quit, Binding::char('+').alt()).unwrap();
+        let typed = |printable: char, alt: bool, text: &str| {
+            let mut key = pressed(KeyCode::Char, printable);
+            key.shift = true;
+            key.alt = alt;
+            key.left_alt = alt;
+            let mut text_key = key;
+            text_key.code = KeyCode::Text;
+            text_key.set_text(text);
+            (key, text_key)
+        };
+        let (shift_3, hash) = typed('3', false, "#");
+        assert_eq!(map.key_action(&shift_3), None);
+        assert_eq!(map.key_action(&hash), Some(&Action::Select));
+        let (alt_shift_equals, plus) = typed('=', true, "+");
+        assert_eq!(map.key_action(&alt_shift_equals), None);
+        assert_eq!(map.key_action(&plus), Some(&Action::Quit));
+        // The text of a key that matched on its own doesn't match again
+        let mut k_text = pressed(KeyCode::Text, 'k');
+        k_text.set_text("k");
+        assert_eq!(map.key_action(&k_text), None);
+
+        let click = Mouse { lbutton_pressed: true, ..Default::default() };
+        assert_eq!(map.action(&Event::Mouse(click)), Some(&Action::Select));
+    }
+
+    #[test]
+    fn config_round_trip() {
+        let config = "# Comment\nUp = Up, 'k', '#' # trailing comment\nQuit = Ctrl+'q', Escape\n\
+                      Select = MouseLeft\n";
+        let map: InputMap<Action> = InputMap::parse(config).unwrap();
+        assert_eq!(map.bindings(&Action::Up), vec![Binding::key(KeyCode::Up), Binding::char('k'),
+                                                   Binding::char('#')]);
+        assert_eq!(map.to_config(), "Up = Up, 'k', '#'\nQuit = Ctrl+'q', Escape\nSelect = MouseLeft\n");
+
+        assert!(InputMap::<Action>::parse("Up = Up\nQuit = Up").is_err());
+        assert!(InputMap::<Action>::parse("Jump = Spacebar").is_err());
+        assert!(InputMap::<Action>::parse("Up Up").is_err());
+    }
+}
@@ -0,0 +1,501 @@
+//! Recording input events and replaying them deterministically
+//!
+//! An [EventRecorder](./struct.EventRecorder.html) wraps an input source (SDL by default) and
+//! stores every event read from it together with the number of the frame it arrived in. An
+//! [EventReplay](./struct.EventReplay.html) then returns the same events in the same frames,
+//! without touching SDL. Together with a seeded `random::Rng` this reproduces a game session
+//! exactly.
+//!
+//! Both are input sources sharing their state between clones: install one clone with
+//! `input::set_input_source`, so the game reads its events through `input::check_for_event` or
+//! `input::events()` as usual, and use the other to mark the frames:
+//!
+//! ```no_run
+//! use tcod::input;
+//! use tcod::input_recording::{EventRecorder, EventReplay};
+//!
+//! let recorder = EventRecorder::new();
+//! input::set_input_source(recorder.clone());
+//! for _frame in 0..1000 {
+//!     for (_flags, _event) in input::events() {
+//!         // handle the event
+//!     }
+//!     recorder.next_frame();
+//! }
+//! input::reset_input_source();
+//! recorder.save("session.txt").unwrap();
+//!
+//! let replay = EventReplay::load("session.txt").unwrap();
+//! input::set_input_source(replay.clone());
+//! while !replay.is_finished() {
+//!     for (_flags, _event) in input::events() {
+//!         // handle the event exactly as during the recording
+//!     }
+//!     replay.next_frame();
+//! }
+//! input::reset_input_source();
+//! ```
+//!
+//! The recordings are text files with one event per line.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use bindings::keycode_from_u32;
+use input::{Event, EventFlags, InputSource, Key, KeyCode, KeyPressFlags, Mouse, SdlInput, Touch};
+use input::{TouchPhase, TouchPosition, KEY_PRESS, KEY_RELEASE};
+
+/// An event together with the time it was received
+#[derive(Copy, Clone, Debug)]
+pub struct RecordedEvent {
+    /// The number of the frame the event arrived in, starting at 0
+    pub frame: u64,
+    /// The time since the start of the recording
+    pub time: Duration,
+    pub flags: EventFlags,
+    pub event: Event,
+}
+
+impl RecordedEvent {
+    fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let millis = self.time.as_secs() * 1000 + self.time.subsec_millis() as u64;
+        write!(writer, "{} {} {}", self.frame, millis, self.flags.bits())?;
+        match self.event {
+            Event::Key(ref key) => {
+                let text = key.text().bytes().map(|b| format!("{:02x}", b)).collect::<String>();
+                writeln!(writer, " key {} {} {} {} {} {} {} {} {}",
+                         key.code as u32, key.printable as u32,
+                         key.pressed as u8, key.left_alt as u8, key.left_ctrl as u8,
+                         key.right_alt as u8, key.right_ctrl as u8, key.shift as u8,
+                         if text.is_empty() { "-" } else { &text })
+            }
+            Event::Mouse(ref mouse) => {
+                writeln!(writer, " mouse {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+                         mouse.x, mouse.y, mouse.dx, mouse.dy,
+                         mouse.cx, mouse.cy, mouse.dcx, mouse.dcy,
+                         mouse.lbutton as u8, mouse.rbutton as u8, mouse.mbutton as u8,
+                         mouse.lbutton_pressed as u8, mouse.rbutton_pressed as u8,
+                         mouse.mbutton_pressed as u8,
+                         mouse.wheel_up as u8, mouse.wheel_down as u8)
+            }
+            Event::Touch(ref touch) => {
+                let phase = match touch.phase {
+                    TouchPhase::Press => "press",
+                    TouchPhase::Move => "move",
+                    TouchPhase::Release => "release",
+                };
+                let p = touch.position;
+                writeln!(writer, " touch {} {} {} {} {} {} {} {} {} {}",
+                         phase, touch.finger, p.x, p.y, p.dx, p.dy, p.cx, p.cy, p.dcx, p.dcy)
+            }
+            Event::__Nonexhaustive => unreachable!(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<RecordedEvent> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let number = |index: usize| fields.get(index).and_then(|f| f.parse::<i64>().ok());
+        let flag = |index: usize| match fields.get(index) {
+            Some(&"0") => Some(false),
+            Some(&"1") => Some(true),
+            _ => None,
+        };
+
+        let frame = number(0)? as u64;
+        let time = Duration::from_millis(number(1)? as u64);
+        let flags = EventFlags::from_bits(number(2)? as u32)?;
+        let event = match fields.get(3) {
+            Some(&"key") if fields.len() == 13 => {
+                let mut key = Key::default();
+                key.code = keycode_from_u32(number(4)? as u32)?;
+                key.printable = ::std::char::from_u32(number(5)? as u32)?;
+                key.pressed = flag(6)?;
+                key.left_alt = flag(7)?;
+                key.left_ctrl = flag(8)?;
+                key.right_alt = flag(9)?;
+                key.right_ctrl = flag(10)?;
+                key.shift = flag(11)?;
+                key.alt = key.left_alt || key.right_alt;
+                key.ctrl = key.left_ctrl || key.right_ctrl;
+                if fields[12] != "-" {
+                    let hex = fields[12].as_bytes();
+                    let bytes = hex.chunks(2)
+                        .map(|pair| {
+                            let pair = ::std::str::from_utf8(pair).ok()?;
+                            if pair.len() == 2 { u8::from_str_radix(pair, 16).ok() } else { None }
+                        })
+                        .collect::<Option<Vec<u8>>>()?;
+                    key.set_text(::std::str::from_utf8(&bytes).ok()?);
+                }
+                Event::Key(key)
+            }
+            Some(&"mouse") if fields.len() == 20 => {
+                Event::Mouse(Mouse {
+                    x: number(4)? as isize,
+                    y: number(5)? as isize,
+                    dx: number(6)? as isize,
+                    dy: number(7)? as isize,
+                    cx: number(8)? as isize,
+                    cy: number(9)? as isize,
+                    dcx: number(10)? as isize,
+                    dcy: number(11)? as isize,
+                    lbutton: flag(12)?,
+                    rbutton: flag(13)?,
+                    mbutton: flag(14)?,
+                    lbutton_pressed: flag(15)?,
+                    rbutton_pressed: flag(16)?,
+                    mbutton_pressed: flag(17)?,
+                    wheel_up: flag(18)?,
+                    wheel_down: flag(19)?,
+                })
+            }
+            Some(&"touch") if fields.len() == 14 => {
+                let phase = match fields[4] {
+                    "press" => TouchPhase::Press,
+                    "move" => TouchPhase::Move,
+                    "release" => TouchPhase::Release,
+                    _ => return None,
+                };
+                let finger = fields[5].parse::<usize>().ok()?;
+                let position = TouchPosition {
+                    x: number(6)? as isize,
+                    y: number(7)? as isize,
+                    dx: number(8)? as isize,
+                    dy: number(9)? as isize,
+                    cx: number(10)? as isize,
+                    cy: number(11)? as isize,
+                    dcx: number(12)? as isize,
+                    dcy: number(13)? as isize,
+                };
+                Event::Touch(Touch { phase, finger, position })
+            }
+            _ => return None,
+        };
+        Some(RecordedEvent { frame, time, flags, event })
+    }
+}
+
+/// Records the events read from another input source
+///
+/// An `EventRecorder` is a handle to a shared recording: install a clone with
+/// `input::set_input_source` and keep the other one to call `next_frame` and `save`.
+#[derive(Clone)]
+pub struct EventRecorder {
+    recording: Rc<RefCell<Recording>>,
+}
+
+struct Recording {
+    source: Box<dyn InputSource>,
+    events: Vec<RecordedEvent>,
+    frame: u64,
+    start: Instant,
+}
+
+impl Default for EventRecorder {
+    fn default() -> Self {
+        EventRecorder::new()
+    }
+}
+
+impl EventRecorder {
+    /// Creates a recorder of the events coming from SDL.
+    pub fn new() -> Self {
+        EventRecorder::wrap(SdlInput)
+    }
+
+    /// Creates a recorder of the events coming from `source`.
+    pub fn wrap<S: InputSource + 'static>(source: S) -> Self {
+        EventRecorder {
+            recording: Rc::new(RefCell::new(Recording {
+                source: Box::new(source),
+                events: Vec::new(),
+                frame: 0,
+                start: Instant::now(),
+            })),
+        }
+    }
+
+    /// Reads the next event from the wrapped source and records it.
+    pub fn check_for_event(&self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        let result = self.recording.borrow_mut().source.check_for_event(event_mask);
+        if let Some((flags, event)) = result {
+            self.record(flags, event);
+        }
+        result
+    }
+
+    /// Records an event in the current frame.
+    pub fn record(&self, flags: EventFlags, event: Event) {
+        let mut recording = self.recording.borrow_mut();
+        let event = RecordedEvent {
+            frame: recording.frame,
+            time: recording.start.elapsed(),
+            flags,
+            event,
+        };
+        recording.events.push(event);
+    }
+
+    fn record_key(&self, key: Key) {
+        if key.code != KeyCode::NoKey {
+            self.record(if key.pressed { KEY_PRESS } else { KEY_RELEASE }, Event::Key(key));
+        }
+    }
+
+    /// Marks the end of a frame. Call it once per iteration of the game loop.
+    pub fn next_frame(&self) {
+        self.recording.borrow_mut().frame += 1;
+    }
+
+    /// Returns the number of the current frame.
+    pub fn frame(&self) -> u64 {
+        self.recording.borrow().frame
+    }
+
+    /// Returns a copy of the events recorded so far.
+    pub fn events(&self) -> Vec<RecordedEvent> {
+        self.recording.borrow().events.clone()
+    }
+
+    /// Writes the recorded events in the recording file format.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        for event in &self.recording.borrow().events {
+            event.write_to(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Saves the recorded events to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()
+    }
+}
+
+impl InputSource for EventRecorder {
+    fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        EventRecorder::check_for_event(self, event_mask)
+    }
+
+    fn wait_for_event(&mut self, event_mask: EventFlags,
+                      timeout: Option<Duration>) -> Option<(EventFlags, Event)> {
+        let result = self.recording.borrow_mut().source.wait_for_event(event_mask, timeout);
+        if let Some((flags, event)) = result {
+            self.record(flags, event);
+        }
+        result
+    }
+
+    fn wait_for_keypress(&mut self, flush: bool) -> Key {
+        let key = self.recording.borrow_mut().source.wait_for_keypress(flush);
+        self.record_key(key);
+        key
+    }
+
+    fn check_for_keypress(&mut self, status: KeyPressFlags) -> Option<Key> {
+        let key = self.recording.borrow_mut().source.check_for_keypress(status);
+        if let Some(key) = key {
+            self.record_key(key);
+        }
+        key
+    }
+}
+
+/// Replays recorded events in place of another input source
+///
+/// Like `EventRecorder`, an `EventReplay` is a handle to a shared replay: install a clone with
+/// `input::set_input_source` and keep the other one to call `next_frame`.
+#[derive(Clone)]
+pub struct EventReplay {
+    replay: Rc<RefCell<Replay>>,
+}
+
+struct Replay {
+    events: Vec<RecordedEvent>,
+    position: usize,
+    frame: u64,
+}
+
+impl EventReplay {
+    pub fn new(events: Vec<RecordedEvent>) -> Self {
+        EventReplay { replay: Rc::new(RefCell::new(Replay { events, position: 0, frame: 0 })) }
+    }
+
+    /// Reads events in the recording file format. Empty lines and lines starting with `#` are
+    /// ignored.
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Self, Error> {
+        let mut events = vec![];
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let event = RecordedEvent::parse(&line).ok_or_else(|| {
+                Error::new(ErrorKind::InvalidData,
+                           format!("Invalid recorded event on line {}", number + 1))
+            })?;
+            events.push(event);
+        }
+        Ok(EventReplay::new(events))
+    }
+
+    /// Loads the events saved by `EventRecorder::save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        EventReplay::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Returns the next event recorded in the current frame.
+    ///
+    /// Like `input::check_for_event`, events that don't match `event_mask` are discarded.
+    pub fn check_for_event(&self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        let mut replay = self.replay.borrow_mut();
+        while let Some(recorded) = replay.events.get(replay.position).copied() {
+            if recorded.frame > replay.frame {
+                return None;
+            }
+            replay.position += 1;
+            if recorded.flags.intersects(event_mask) {
+                return Some((recorded.flags, recorded.event));
+            }
+        }
+        None
+    }
+
+    /// Moves to the next frame. Any events left in the current frame are discarded.
+    pub fn next_frame(&self) {
+        let mut replay = self.replay.borrow_mut();
+        replay.frame += 1;
+        while replay.events.get(replay.position).is_some_and(|e| e.frame < replay.frame) {
+            replay.position += 1;
+        }
+    }
+
+    /// Returns the number of the current frame.
+    pub fn frame(&self) -> u64 {
+        self.replay.borrow().frame
+    }
+
+    /// Returns whether all the recorded events have been replayed.
+    pub fn is_finished(&self) -> bool {
+        let replay = self.replay.borrow();
+        replay.position >= replay.events.len()
+    }
+}
+
+impl InputSource for EventReplay {
+    fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        EventReplay::check_for_event(self, event_mask)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EventRecorder, EventReplay};
+    use input::{self, Event, Key, KeyCode, Mouse, ScriptedInput, Touch, TouchPhase};
+    use input::TouchPosition;
+    use input::{FINGER, FINGER_MOVE, FINGER_PRESS, KEY, KEY_PRESS, KEY_RELEASE, MOUSE};
+    use input::{MOUSE_PRESS, MOUSE_RELEASE};
+
+    #[test]
+    fn round_trip() {
+        let mut key = Key::default();
+        key.code = KeyCode::Text;
+        key.pressed = true;
+        key.set_text("é!");
+        let mouse = Mouse { x: 120, y: -3, cx: 15, lbutton_pressed: true, ..Default::default() };
+
+        let recorder = EventRecorder::new();
+        recorder.record(KEY_PRESS, Event::Key(key));
+        recorder.next_frame();
+        recorder.next_frame();
+        recorder.record(MOUSE_PRESS, Event::Mouse(mouse));
+        let tap = Touch {
+            phase: TouchPhase::Press,
+            finger: 0,
+            position: TouchPosition { x: 40, y: 64, cx: 5, cy: 8, ..Default::default() },
+        };
+        let pan = Touch {
+            phase: TouchPhase::Move,
+            finger: 1,
+            position: TouchPosition { x: 96, y: 16, dx: -8, dy: 0, cx: 12, cy: 2, dcx: -1, dcy: 0 },
+        };
+        recorder.record(FINGER_PRESS, Event::Touch(tap));
+        recorder.record(FINGER_MOVE, Event::Touch(pan));
+        let mut file = vec![];
+        recorder.write_to(&mut file).unwrap();
+
+        let replay = EventReplay::read_from(&file[..]).unwrap();
+        match replay.check_for_event(KEY | MOUSE) {
+            Some((KEY_PRESS, Event::Key(k))) => {
+                assert_eq!(k.text(), "é!");
+                assert_eq!(k, key);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(replay.check_for_event(KEY | MOUSE).is_none());
+        replay.next_frame();
+        assert!(replay.check_for_event(KEY | MOUSE).is_none());
+        replay.next_frame();
+        match replay.check_for_event(KEY | MOUSE) {
+            Some((MOUSE_PRESS, Event::Mouse(m))) => assert_eq!(m, mouse),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match replay.check_for_event(FINGER) {
+            Some((FINGER_PRESS, Event::Touch(t))) => assert_eq!(t, tap),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match replay.check_for_event(FINGER) {
+            Some((FINGER_MOVE, Event::Touch(t))) => assert_eq!(t, pan),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn mask_discards_events() {
+        let recorder = EventRecorder::new();
+        recorder.record(MOUSE_PRESS, Event::Mouse(Mouse::default()));
+        recorder.record(KEY_PRESS, Event::Key(Key::default()));
+        let replay = EventReplay::new(recorder.events());
+        assert!(replay.check_for_event(KEY).is_some());
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn invalid_lines() {
+        assert!(EventReplay::read_from(&b"# comment\n\n"[..]).unwrap().is_finished());
+        assert!(EventReplay::read_from(&b"0 0 1 key 999 0 1 0 0 0 0 0 -\n"[..]).is_err());
+        assert!(EventReplay::read_from(&b"0 0 1 mouse 1 2\n"[..]).is_err());
+    }
+
+    #[test]
+    fn installed_as_input_sources() {
+        let mut script = ScriptedInput::new();
+        script.press_char('a');
+        script.click(3, 4);
+        let recorder = EventRecorder::wrap(script);
+        input::set_input_source(recorder.clone());
+        assert!(input::check_for_event(KEY_PRESS).is_some());
+        recorder.next_frame();
+        assert_eq!(input::events().count(), 3);
+        input::reset_input_source();
+        assert_eq!(recorder.events().len(), 4);
+
+        let mut file = vec![];
+        recorder.write_to(&mut file).unwrap();
+        let replay = EventReplay::read_from(&file[..]).unwrap();
+        input::set_input_source(replay.clone());
+        let flags: Vec<_> = input::events().map(|(flags, _)| flags).collect();
+        assert_eq!(flags, [KEY_PRESS]);
+        replay.next_frame();
+        let flags: Vec<_> = input::events().map(|(flags, _)| flags).collect();
+        assert_eq!(flags, [KEY_RELEASE, MOUSE_PRESS, MOUSE_RELEASE]);
+        input::reset_input_source();
+        assert!(replay.is_finished());
+    }
+}
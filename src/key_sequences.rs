@@ -0,0 +1,403 @@
+//! Key auto-repeat and multi-key sequences
+//!
+//! [KeyRepeat](./struct.KeyRepeat.html) repeats a held key with a configurable delay and rate,
+//! independent of the operating system's keyboard settings.
+//!
+//! [KeySequences](./struct.KeySequences.html) recognizes sequences of key chords such as the vi
+//! style `g g` or `Escape :` and returns the action bound to them:
+//!
+//! ```no_run
+//! use tcod::input::{self, Event};
+//! use tcod::key_sequences::KeySequences;
+//!
+//! #[derive(Clone, PartialEq, Debug)]
+//! enum Command { Top, Bottom, CommandLine }
+//!
+//! let mut sequences = KeySequences::new();
+//! sequences.bind("g g", Command::Top).unwrap();
+//! sequences.bind("Shift+g", Command::Bottom).unwrap();
+//! sequences.bind("Escape :", Command::CommandLine).unwrap();
+//!
+//! loop {
+//!     for (_, event) in input::events() {
+//!         if let Event::Key(key) = event {
+//!             for command in sequences.handle_key(&key) {
+//!                 println!("{:?}", command);
+//!             }
+//!         }
+//!     }
+//!     for command in sequences.poll() {
+//!         println!("{:?}", command);
+//!     }
+//! }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use input::{Key, KeyChord, KeyCode, ParseKeyError};
+
+fn is_modifier(code: KeyCode) -> bool {
+    matches!(code, KeyCode::Shift | KeyCode::Control | KeyCode::Alt)
+}
+
+/// Repeats held keys
+///
+/// Pass all the key events to `handle_key` and call `poll` every frame. The first press of a key
+/// is returned right away, then the key is repeated after `delay` and every `interval` after
+/// that, for as long as it's held. The operating system's own repeated presses are dropped.
+#[derive(Clone, Debug)]
+pub struct KeyRepeat {
+    delay: Duration,
+    interval: Duration,
+    held: Option<(Key, Instant)>,
+}
+
+impl KeyRepeat {
+    pub fn new(delay: Duration, interval: Duration) -> KeyRepeat {
+        assert!(interval > Duration::from_millis(0), "the repeat interval must not be zero");
+        KeyRepeat { delay, interval, held: None }
+    }
+
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        assert!(interval > Duration::from_millis(0), "the repeat interval must not be zero");
+        self.interval = interval;
+    }
+
+    /// Processes a key event. Returns the key if it was just pressed.
+    pub fn handle_key(&mut self, key: &Key) -> Option<Key> {
+        self.handle_key_at(key, Instant::now())
+    }
+
+    /// Like `handle_key`, with the time the event was received at.
+    pub fn handle_key_at(&mut self, key: &Key, now: Instant) -> Option<Key> {
+        if is_modifier(key.code) || key.code == KeyCode::Text {
+            return None;
+        }
+        let same_key = match self.held {
+            Some((ref held, _)) => held.code == key.code && held.printable == key.printable,
+            None => false,
+        };
+        if key.pressed {
+            if same_key {
+                return None;
+            }
+            self.held = Some((*key, now + self.delay));
+            Some(*key)
+        } else {
+            if same_key {
+                self.held = None;
+            }
+            None
+        }
+    }
+
+    /// Returns the repeated presses of the held key that are due.
+    pub fn poll(&mut self) -> Vec<Key> {
+        self.poll_at(Instant::now())
+    }
+
+    /// Like `poll`, at the given time.
+    pub fn poll_at(&mut self, now: Instant) -> Vec<Key> {
+        let mut repeats = vec![];
+        if let Some((key, ref mut next)) = self.held {
+            while *next <= now {
+                repeats.push(key);
+                *next += self.interval;
+            }
+        }
+        repeats
+    }
+
+    /// Stops repeating, e.g. after the window lost focus.
+    pub fn clear(&mut self) {
+        self.held = None;
+    }
+}
+
+/// Recognizes sequences of key chords and returns the actions bound to them
+///
+/// A sequence has to be typed with at most `timeout` between two keys. When a sequence is also
+/// the beginning of a longer one (like `g` and `g g`), the recognizer waits for the next key or
+/// the timeout before deciding. Releases and the modifier keys on their own are ignored.
+///
+/// libtcod reports a character typed with Shift as the unshifted key with `shift` set, followed
+/// by a `KeyCode::Text` event with the character itself (`Shift+;` then `:` on a US layout). Both
+/// forms match, so `"Escape :"` and `"Escape Shift+;"` are typed the same way. For this, a
+/// character key is only looked at once the next key event arrives or `poll` is called, so pass
+/// all the key events of a frame before calling `poll`.
+#[derive(Clone, Debug)]
+pub struct KeySequences<A> {
+    sequences: Vec<(Vec<KeyChord>, A)>,
+    timeout: Duration,
+    /// The chords each key typed so far can stand for
+    typed: Vec<Vec<KeyChord>>,
+    /// A character key waiting for its text event
+    pending: Option<(KeyChord, Instant)>,
+    last_key: Option<Instant>,
+}
+
+impl<A: Clone> Default for KeySequences<A> {
+    fn default() -> Self {
+        KeySequences::new()
+    }
+}
+
+impl<A: Clone> KeySequences<A> {
+    /// Creates a recognizer with a one second timeout.
+    pub fn new() -> Self {
+        KeySequences {
+            sequences: Vec::new(),
+            timeout: Duration::from_secs(1),
+            typed: Vec::new(),
+            pending: None,
+            last_key: None,
+        }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Binds a sequence of whitespace-separated `KeyChord`s, e.g. `"g g"` or `"Ctrl+x Ctrl+s"`.
+    /// Binding the same sequence again replaces its action.
+    pub fn bind(&mut self, sequence: &str, action: A) -> Result<(), ParseKeyError> {
+        let chords = sequence.split_whitespace()
+            .map(|chord| chord.parse())
+            .collect::<Result<Vec<KeyChord>, _>>()?;
+        self.bind_chords(chords, action);
+        Ok(())
+    }
+
+    /// Binds a sequence of chords. Binding the same sequence again replaces its action.
+    pub fn bind_chords(&mut self, chords: Vec<KeyChord>, action: A) {
+        assert!(!chords.is_empty(), "a key sequence needs at least one key");
+        self.sequences.retain(|(sequence, _)| *sequence != chords);
+        self.sequences.push((chords, action));
+    }
+
+    /// Forgets the keys typed so far.
+    pub fn reset(&mut self) {
+        self.typed.clear();
+        self.pending = None;
+        self.last_key = None;
+    }
+
+    /// Processes a key event and returns the actions of the completed sequences.
+    pub fn handle_key(&mut self, key: &Key) -> Vec<A> {
+        self.handle_key_at(key, Instant::now())
+    }
+
+    /// Like `handle_key`, with the time the event was received at.
+    pub fn handle_key_at(&mut self, key: &Key, now: Instant) -> Vec<A> {
+        if key.code == KeyCode::Text {
+            return match self.pending.take() {
+                Some((chord, time)) => {
+                    let mut alternatives = vec![chord];
+                    let mut chars = key.text().chars();
+                    if let (Some(printable), None) = (chars.next(), chars.next()) {
+                        let text = KeyChord { printable, shift: false, ..chord };
+                        if text != chord {
+                            alternatives.push(text);
+                        }
+                    }
+                    self.type_key(alternatives, time)
+                }
+                None => {
+                    // Text without a key press, e.g. from an input method
+                    let mut actions = vec![];
+                    for printable in key.text().chars() {
+                        let chord = KeyChord { printable, ..KeyChord::new(KeyCode::Char) };
+                        actions.extend(self.type_key(vec![chord], now));
+                    }
+                    actions
+                }
+            };
+        }
+        let mut actions = self.poll_at(now);
+        if !key.pressed || is_modifier(key.code) {
+            return actions;
+        }
+        let chord = KeyChord::from(*key);
+        if key.code == KeyCode::Char {
+            self.pending = Some((chord, now));
+        } else {
+            actions.extend(self.type_key(vec![chord], now));
+        }
+        actions
+    }
+
+    /// Adds a key to the typed sequence and returns the actions of the completed sequences.
+    fn type_key(&mut self, alternatives: Vec<KeyChord>, now: Instant) -> Vec<A> {
+        let mut actions = self.expire(now);
+        self.typed.push(alternatives);
+        self.last_key = Some(now);
+        if !self.is_prefix(&self.typed) && self.exact_match(&self.typed).is_none() {
+            // The new key doesn't continue the sequence: finish what was typed before it and
+            // start over from the new key.
+            let alternatives = self.typed.pop().unwrap();
+            actions.extend(self.exact_match(&self.typed));
+            self.typed = vec![alternatives];
+        }
+        if !self.is_prefix(&self.typed) {
+            actions.extend(self.exact_match(&self.typed));
+            self.typed.clear();
+        }
+        actions
+    }
+
+    /// Returns the action of a sequence that's waiting for a longer match once the timeout has
+    /// passed. Call this every frame, after handling the frame's key events.
+    pub fn poll(&mut self) -> Vec<A> {
+        self.poll_at(Instant::now())
+    }
+
+    /// Like `poll`, at the given time.
+    pub fn poll_at(&mut self, now: Instant) -> Vec<A> {
+        let mut actions = match self.pending.take() {
+            Some((chord, time)) => self.type_key(vec![chord], time),
+            None => vec![],
+        };
+        actions.extend(self.expire(now));
+        actions
+    }
+
+    /// Finishes the typed sequence if the timeout has passed at `now`.
+    fn expire(&mut self, now: Instant) -> Vec<A> {
+        let timed_out = match self.last_key {
+            Some(last) => now.duration_since(last) > self.timeout,
+            None => false,
+        };
+        if !timed_out || self.typed.is_empty() {
+            return vec![];
+        }
+        let action = self.exact_match(&self.typed);
+        self.typed.clear();
+        self.last_key = None;
+        action.into_iter().collect()
+    }
+
+    /// Returns whether `sequence` begins with the keys in `typed`.
+    fn starts_with(sequence: &[KeyChord], typed: &[Vec<KeyChord>]) -> bool {
+        sequence.len() >= typed.len() &&
+            sequence.iter().zip(typed).all(|(chord, alternatives)| alternatives.contains(chord))
+    }
+
+    fn exact_match(&self, typed: &[Vec<KeyChord>]) -> Option<A> {
+        self.sequences.iter()
+            .find(|(sequence, _)| sequence.len() == typed.len() &&
+                  KeySequences::<A>::starts_with(sequence, typed))
+            .map(|(_, action)| action.clone())
+    }
+
+    /// Returns whether `typed` is the beginning of a longer sequence.
+    fn is_prefix(&self, typed: &[Vec<KeyChord>]) -> bool {
+        self.sequences.iter()
+            .any(|(sequence, _)| sequence.len() > typed.len() &&
+                 KeySequences::<A>::starts_with(sequence, typed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KeyRepeat, KeySequences};
+    use input::{Key, KeyCode};
+    use std::time::{Duration, Instant};
+
+    fn key(code: KeyCode, printable: char, pressed: bool) -> Key {
+        let mut key = Key::default();
+        key.code = code;
+        key.printable = printable;
+        key.pressed = pressed;
+        key
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn repeat_held_key() {
+        let start = Instant::now();
+        let mut repeat = KeyRepeat::new(ms(300), ms(100));
+        let up = key(KeyCode::Up, '\0', true);
+        assert_eq!(repeat.handle_key_at(&up, start), Some(up));
+        assert_eq!(repeat.poll_at(start + ms(299)).len(), 0);
+        // The operating system's repeated presses are ignored
+        assert_eq!(repeat.handle_key_at(&up, start + ms(250)), None);
+        assert_eq!(repeat.poll_at(start + ms(300)).len(), 1);
+        assert_eq!(repeat.poll_at(start + ms(520)).len(), 2);
+        repeat.handle_key_at(&key(KeyCode::Up, '\0', false), start + ms(550));
+        assert_eq!(repeat.poll_at(start + ms(1000)).len(), 0);
+    }
+
+    /// The events libtcod sends for a character key: the press, the text and the release
+    fn type_char(sequences: &mut KeySequences<&'static str>, printable: char, shift: bool,
+                 text: &str, now: Instant) -> Vec<&'static str> {
+        let mut press = key(KeyCode::Char, printable, true);
+        press.shift = shift;
+        let mut text_key = press;
+        text_key.code = KeyCode::Text;
+        text_key.set_text(text);
+        let mut release = press;
+        release.pressed = false;
+        let mut actions = sequences.handle_key_at(&press, now);
+        actions.extend(sequences.handle_key_at(&text_key, now));
+        actions.extend(sequences.handle_key_at(&release, now));
+        actions
+    }
+
+    #[test]
+    fn sequences() {
+        let start = Instant::now();
+        let mut sequences = KeySequences::new();
+        sequences.bind("g g", "top").unwrap();
+        sequences.bind("g", "go").unwrap();
+        sequences.bind("Escape :", "command").unwrap();
+        sequences.bind("x", "delete").unwrap();
+        assert!(sequences.bind("g Foo", "invalid").is_err());
+
+        assert!(type_char(&mut sequences, 'g', false, "g", start).is_empty());
+        assert_eq!(type_char(&mut sequences, 'g', false, "g", start + ms(100)), vec!["top"]);
+
+        // "g" alone fires once the timeout passes
+        assert!(type_char(&mut sequences, 'g', false, "g", start + ms(200)).is_empty());
+        assert_eq!(sequences.poll_at(start + ms(1300)), vec!["go"]);
+
+        // A key that doesn't continue the sequence finishes the previous one
+        assert!(type_char(&mut sequences, 'g', false, "g", start + ms(2000)).is_empty());
+        assert_eq!(type_char(&mut sequences, 'x', false, "x", start + ms(2100)),
+                   vec!["go", "delete"]);
+
+        // ':' comes as Shift+; followed by its text
+        let escape = key(KeyCode::Escape, '\u{1b}', true);
+        let shift = key(KeyCode::Shift, '\0', true);
+        assert!(sequences.handle_key_at(&escape, start + ms(3000)).is_empty());
+        assert!(sequences.handle_key_at(&shift, start + ms(3050)).is_empty());
+        assert_eq!(type_char(&mut sequences, ';', true, ":", start + ms(3100)), vec!["command"]);
+
+        // Too slow
+        assert!(sequences.handle_key_at(&escape, start + ms(4000)).is_empty());
+        assert!(type_char(&mut sequences, ';', true, ":", start + ms(5500)).is_empty());
+    }
+
+    #[test]
+    fn shifted_characters() {
+        let start = Instant::now();
+        let mut sequences = KeySequences::new();
+        sequences.bind("Shift+g", "bottom").unwrap();
+        sequences.bind("Escape Shift+;", "command").unwrap();
+        assert_eq!(type_char(&mut sequences, 'g', true, "G", start), vec!["bottom"]);
+        assert!(sequences.handle_key_at(&key(KeyCode::Escape, '\u{1b}', true), start).is_empty());
+        assert_eq!(type_char(&mut sequences, ';', true, ":", start), vec!["command"]);
+
+        // Keys without text events, as pushed by `ScriptedInput`, are handled on the next event
+        let mut sequences = KeySequences::new();
+        sequences.bind("x", "delete").unwrap();
+        assert!(sequences.handle_key_at(&key(KeyCode::Char, 'x', true), start).is_empty());
+        assert_eq!(sequences.poll_at(start), vec!["delete"]);
+    }
+}
@@ -44,6 +44,8 @@
 #[macro_use] extern crate lazy_static;
 #[cfg(feature = "rustc-serialize")] extern crate rustc_serialize;
 #[cfg(feature = "serialization")] extern crate serde;
+#[cfg(feature = "recording")] extern crate gif;
+#[cfg(feature = "recording")] extern crate png;
 #[macro_use]
 #[cfg(feature = "serialization")] extern crate serde_derive;
 
@@ -57,20 +59,33 @@ pub use colors::Color;
 pub use console::{Console, RootInitializer, BackgroundFlag, Renderer, FontLayout, FontType, TextAlignment};
 pub use map::Map;
 
+pub mod app;
 pub mod bsp;
 pub mod chars;
 pub mod colors;
 pub mod console;
+pub mod explored;
+pub mod fov;
 pub mod heightmap;
 pub mod image;
 pub mod input;
+pub mod input_map;
+pub mod input_recording;
+pub mod key_sequences;
+pub mod lighting;
 pub mod line;
 pub mod map;
+pub mod mouse_regions;
 pub mod namegen;
 pub mod noise;
 pub mod pathfinding;
 pub mod random;
+#[cfg(feature = "recording")]
+pub mod recording;
+pub mod scene;
 pub mod system;
+pub mod text_field;
+pub mod touch_gestures;
 
 mod bindings;
 #[macro_use]
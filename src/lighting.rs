@@ -0,0 +1,243 @@
+//! Colored lights blocked by walls
+//!
+//! A [LightMap](./struct.LightMap.html) adds up the light of many
+//! [Light](./struct.Light.html)s for every cell of a map, then tints a console with it:
+//!
+//! ```no_run
+//! use tcod::colors::Color;
+//! use tcod::console::Offscreen;
+//! use tcod::lighting::{Falloff, Light, LightMap};
+//! use tcod::map::Map;
+//!
+//! let mut map = Map::new(80, 50);
+//! map.clear(true, true);
+//! let mut console = Offscreen::new(80, 50);
+//!
+//! let mut lights = LightMap::new(80, 50);
+//! lights.set_ambient(Color::new(20, 20, 30));
+//! lights.add_light(Light::new(10, 10, 8, Color::new(255, 160, 60)).falloff(Falloff::Quadratic));
+//! lights.add_light(Light::new(40, 25, 12, Color::new(80, 120, 255)).intensity(0.7));
+//! lights.compute(&map);
+//! lights.apply(&mut console);
+//! ```
+//!
+//! The lights only reach the cells they can see (computed with `fov::compute_symmetric`), so
+//! walls cast shadows. A flickering torch is a light whose position and intensity change a bit
+//! every frame.
+
+use colors::Color;
+use console::{BackgroundFlag, Console};
+use fov;
+use map::Map;
+
+/// How the light gets weaker with the distance
+#[derive(Copy, Clone, Debug)]
+pub enum Falloff {
+    /// Full brightness up to the radius
+    Constant,
+    /// Fades linearly to nothing at the radius
+    Linear,
+    /// Fades quickly near the light and slowly towards the radius
+    Quadratic,
+    /// Stays bright near the light and fades towards the radius
+    Smooth,
+    /// Maps the distance relative to the radius (from 0.0 to 1.0) to the brightness
+    Custom(fn(f32) -> f32),
+}
+
+impl Falloff {
+    /// Returns the brightness at `distance`, which is relative to the radius.
+    pub fn brightness(self, distance: f32) -> f32 {
+        let distance = distance.clamp(0.0, 1.0);
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - distance,
+            Falloff::Quadratic => (1.0 - distance) * (1.0 - distance),
+            Falloff::Smooth => 1.0 - distance * distance,
+            Falloff::Custom(curve) => curve(distance),
+        }
+    }
+}
+
+/// A light source
+#[derive(Copy, Clone, Debug)]
+pub struct Light {
+    pub x: i32,
+    pub y: i32,
+    pub radius: i32,
+    pub color: Color,
+    pub intensity: f32,
+    pub falloff: Falloff,
+}
+
+impl Light {
+    /// Creates a light with an intensity of 1.0 and a linear falloff.
+    pub fn new(x: i32, y: i32, radius: i32, color: Color) -> Light {
+        assert!(radius > 0);
+        Light { x, y, radius, color, intensity: 1.0, falloff: Falloff::Linear }
+    }
+
+    pub fn intensity(mut self, intensity: f32) -> Light {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn falloff(mut self, falloff: Falloff) -> Light {
+        self.falloff = falloff;
+        self
+    }
+}
+
+/// The light accumulated in every cell of a map
+#[derive(Clone, Debug)]
+pub struct LightMap {
+    width: i32,
+    height: i32,
+    ambient: Color,
+    lights: Vec<Light>,
+    cells: Vec<[f32; 3]>,
+}
+
+impl LightMap {
+    /// Creates a dark `width` x `height` light map without lights.
+    pub fn new(width: i32, height: i32) -> LightMap {
+        assert!(width > 0 && height > 0);
+        LightMap {
+            width,
+            height,
+            ambient: Color::new(0, 0, 0),
+            lights: Vec::new(),
+            cells: vec![[0.0; 3]; (width * height) as usize],
+        }
+    }
+
+    /// Sets the light every cell gets, including the ones no light reaches.
+    pub fn set_ambient(&mut self, ambient: Color) {
+        self.ambient = ambient;
+    }
+
+    /// Adds a light and returns its index in `lights`.
+    pub fn add_light(&mut self, light: Light) -> usize {
+        self.lights.push(light);
+        self.lights.len() - 1
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    /// Gives access to the lights, e.g. to move them around.
+    pub fn lights_mut(&mut self) -> &mut Vec<Light> {
+        &mut self.lights
+    }
+
+    pub fn clear_lights(&mut self) {
+        self.lights.clear();
+    }
+
+    /// Computes the light of every cell. The non-transparent cells of `map` block the light.
+    pub fn compute(&mut self, map: &Map) {
+        let (width, height) = map.size();
+        assert!(width == self.width && height == self.height, "the map has a different size");
+        self.compute_with(|x, y| !map.is_transparent(x, y));
+    }
+
+    /// Computes the light of every cell with the cells for which `is_opaque(x, y)` returns true
+    /// blocking the light.
+    pub fn compute_with<F>(&mut self, is_opaque: F) where F: Fn(i32, i32) -> bool {
+        let ambient = [self.ambient.r as f32, self.ambient.g as f32, self.ambient.b as f32];
+        for cell in &mut self.cells {
+            *cell = ambient;
+        }
+        for light in &self.lights {
+            if light.x < 0 || light.y < 0 || light.x >= self.width || light.y >= self.height {
+                continue;
+            }
+            let visibility = fov::compute_symmetric(self.width, self.height, (light.x, light.y),
+                                                    light.radius, true, &is_opaque);
+            for (x, y) in visibility.visible_cells() {
+                let (dx, dy) = ((x - light.x) as f32, (y - light.y) as f32);
+                let distance = (dx * dx + dy * dy).sqrt() / light.radius as f32;
+                let brightness = light.intensity * light.falloff.brightness(distance);
+                if brightness <= 0.0 {
+                    continue;
+                }
+                let cell = &mut self.cells[(y * self.width + x) as usize];
+                cell[0] += light.color.r as f32 * brightness;
+                cell[1] += light.color.g as f32 * brightness;
+                cell[2] += light.color.b as f32 * brightness;
+            }
+        }
+    }
+
+    /// Returns the light of a cell. Channels brighter than 255 are capped.
+    pub fn light_at(&self, x: i32, y: i32) -> Color {
+        assert!(x >= 0 && y >= 0 && x < self.width && y < self.height);
+        let cell = self.cells[(y * self.width + x) as usize];
+        let channel = |value: f32| value.clamp(0.0, 255.0).round() as u8;
+        Color::new(channel(cell[0]), channel(cell[1]), channel(cell[2]))
+    }
+
+    /// Tints a color with the light of a cell: full white light leaves it as it is, darkness
+    /// makes it black.
+    pub fn shade(&self, x: i32, y: i32, color: Color) -> Color {
+        let light = self.light_at(x, y);
+        let channel = |color: u8, light: u8| (color as u32 * light as u32 / 255) as u8;
+        Color::new(channel(color.r, light.r), channel(color.g, light.g),
+                   channel(color.b, light.b))
+    }
+
+    /// Tints the background and foreground colors of a console. The console's cells are matched
+    /// with the light map's cells from the top left corner.
+    pub fn apply<C: Console>(&self, console: &mut C) {
+        let width = self.width.min(console.width());
+        let height = self.height.min(console.height());
+        for y in 0..height {
+            for x in 0..width {
+                let background = self.shade(x, y, console.get_char_background(x, y));
+                let foreground = self.shade(x, y, console.get_char_foreground(x, y));
+                console.set_char_background(x, y, background, BackgroundFlag::Set);
+                console.set_char_foreground(x, y, foreground);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Falloff, Light, LightMap};
+    use colors::Color;
+
+    #[test]
+    fn falloff_curves() {
+        assert_eq!(Falloff::Constant.brightness(0.9), 1.0);
+        assert_eq!(Falloff::Linear.brightness(0.25), 0.75);
+        assert_eq!(Falloff::Quadratic.brightness(0.5), 0.25);
+        assert_eq!(Falloff::Smooth.brightness(0.5), 0.75);
+        assert_eq!(Falloff::Linear.brightness(2.0), 0.0);
+        fn half(_: f32) -> f32 { 0.5 }
+        assert_eq!(Falloff::Custom(half).brightness(0.1), 0.5);
+    }
+
+    #[test]
+    fn accumulated_light() {
+        let mut lights = LightMap::new(11, 3);
+        lights.set_ambient(Color::new(10, 10, 10));
+        lights.add_light(Light::new(2, 1, 4, Color::new(200, 0, 0)).falloff(Falloff::Constant));
+        lights.add_light(Light::new(8, 1, 4, Color::new(0, 100, 0)).intensity(0.5)
+                         .falloff(Falloff::Constant));
+        // A wall at x = 5 keeps the lights apart
+        lights.compute_with(|x, _| x == 5);
+        assert_eq!(lights.light_at(2, 1), Color::new(210, 10, 10));
+        assert_eq!(lights.light_at(4, 0), Color::new(210, 10, 10));
+        assert_eq!(lights.light_at(9, 2), Color::new(10, 60, 10));
+        assert_eq!(lights.light_at(6, 1), Color::new(10, 60, 10));
+        // The wall is lit from both sides
+        assert_eq!(lights.light_at(5, 1), Color::new(210, 60, 10));
+        assert_eq!(lights.shade(2, 1, Color::new(255, 255, 255)), Color::new(210, 10, 10));
+
+        lights.add_light(Light::new(3, 1, 2, Color::new(255, 255, 255)).intensity(2.0));
+        lights.compute_with(|x, _| x == 5);
+        assert_eq!(lights.light_at(3, 1), Color::new(255, 255, 255));
+    }
+}
@@ -1,5 +1,12 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::slice;
+
 use bindings::ffi::{self, TCOD_fov_algorithm_t};
 use bindings::{AsNative, c_bool};
+use fov;
+use line::Line;
 
 pub struct Map {
     tcod_map: ffi::TCOD_map_t,
@@ -23,6 +30,71 @@ impl Map {
         }
     }
 
+    /// Builds a map from text, one line per row, e.g. for test fixtures or prefab rooms:
+    ///
+    /// ```no_run
+    /// use tcod::map::{Map, MapLegend};
+    ///
+    /// let (map, markers) = Map::from_str("
+    /// #######
+    /// #@....#
+    /// #..#.>#
+    /// #######
+    /// ", &MapLegend::default()).unwrap();
+    /// assert_eq!(markers.get('@'), Some((1, 1)));
+    /// assert!(!map.is_walkable(3, 2));
+    /// ```
+    ///
+    /// Blank lines at the start and at the end are ignored. Returns the map along with the
+    /// positions of the legend's markers.
+    pub fn from_str(text: &str, legend: &MapLegend) -> Result<(Map, Markers), ParseMapError> {
+        let mut lines: Vec<&str> = text.lines().collect();
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        let start = lines.iter().position(|line| !line.trim().is_empty()).unwrap_or(lines.len());
+        Map::from_lines(&lines[start..], legend)
+    }
+
+    /// Builds a map from rows of characters looked up in `legend`. All the rows must have the
+    /// same width.
+    pub fn from_lines<S: AsRef<str>>(lines: &[S], legend: &MapLegend)
+                                     -> Result<(Map, Markers), ParseMapError> {
+        let width = match lines.first() {
+            Some(line) => line.as_ref().chars().count() as i32,
+            None => return Err(ParseMapError::Empty),
+        };
+        if width == 0 {
+            return Err(ParseMapError::Empty);
+        }
+        let mut cells = Vec::with_capacity((width as usize) * lines.len());
+        let mut markers = Markers::default();
+        for (y, line) in lines.iter().enumerate() {
+            let y = y as i32;
+            let line = line.as_ref();
+            let row_width = line.chars().count() as i32;
+            if row_width != width {
+                return Err(ParseMapError::UnevenRow { row: y, width: row_width, expected: width });
+            }
+            for (x, ch) in line.chars().enumerate() {
+                let x = x as i32;
+                let symbol = match legend.symbols.get(&ch) {
+                    Some(symbol) => symbol,
+                    None => return Err(ParseMapError::UnknownCharacter { ch, x, y }),
+                };
+                if symbol.marker {
+                    markers.positions.push((ch, (x, y)));
+                }
+                cells.push((x, y, symbol.transparent, symbol.walkable));
+            }
+        }
+        let mut map = Map::new(width, lines.len() as i32);
+        for (x, y, transparent, walkable) in cells {
+            map.set(x, y, transparent, walkable);
+        }
+        Ok((map, markers))
+    }
+
     pub fn size(&self) -> (i32, i32) {
         unsafe {
             (ffi::TCOD_map_get_width(self.tcod_map),
@@ -51,6 +123,101 @@ impl Map {
         }
     }
 
+    /// Computes the field of view like `compute_fov`, then keeps only the cells within
+    /// `half_angle` of the `facing_angle` direction, e.g. for a guard's sight or a flashlight.
+    ///
+    /// The angles are in radians, see `fov::is_in_cone`. The origin is always in the field of
+    /// view.
+    pub fn compute_fov_cone(&mut self, (origin_x, origin_y): (i32, i32), facing_angle: f32,
+                            half_angle: f32, max_radius: i32, light_walls: bool,
+                            algo: FovAlgorithm) {
+        self.compute_fov(origin_x, origin_y, max_radius, light_walls, algo);
+        let (width, height) = self.size();
+        let (min_x, min_y, max_x, max_y) = if max_radius > 0 {
+            ((origin_x - max_radius).max(0), (origin_y - max_radius).max(0),
+             (origin_x + max_radius).min(width - 1), (origin_y + max_radius).min(height - 1))
+        } else {
+            (0, 0, width - 1, height - 1)
+        };
+        for y in min_y..max_y + 1 {
+            for x in min_x..max_x + 1 {
+                if !fov::is_in_cone(x - origin_x, y - origin_y, facing_angle, half_angle) &&
+                    self.is_in_fov(x, y) {
+                    self.set_in_fov(x, y, false);
+                }
+            }
+        }
+    }
+
+    /// Computes the field of view with the symmetric shadowcasting of `fov::compute_symmetric`
+    /// instead of libtcod. The result is read with `is_in_fov` as usual.
+    pub fn compute_fov_symmetric(&mut self, origin_x: i32, origin_y: i32, max_radius: i32,
+                                 light_walls: bool) {
+        let (width, height) = self.size();
+        let visibility = fov::compute_symmetric(width, height, (origin_x, origin_y), max_radius,
+                                                light_walls, |x, y| !self.is_transparent(x, y));
+        for y in 0..height {
+            for x in 0..width {
+                self.set_in_fov(x, y, visibility.is_visible(x, y));
+            }
+        }
+    }
+
+    /// Returns whether `to` can be seen from `from` without computing a whole field of view.
+    ///
+    /// This uses `LineOfSight::SymmetricBresenham`, so a monster sees the player exactly when the
+    /// player sees it.
+    pub fn has_los(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        self.has_los_with(from, to, LineOfSight::SymmetricBresenham)
+    }
+
+    /// Like `has_los`, tracing the sight the way `mode` does.
+    pub fn has_los_with(&self, from: (i32, i32), to: (i32, i32), mode: LineOfSight) -> bool {
+        let (width, height) = self.size();
+        assert!(from.0 >= 0 && from.1 >= 0 && from.0 < width && from.1 < height);
+        assert!(to.0 >= 0 && to.1 >= 0 && to.0 < width && to.1 < height);
+        match mode {
+            LineOfSight::Bresenham => self.line_is_clear(from, to),
+            LineOfSight::SymmetricBresenham => {
+                self.line_is_clear(from, to) || self.line_is_clear(to, from)
+            }
+            LineOfSight::Shadowcasting => {
+                fov::is_visible_symmetric(from, to, |x, y| !self.is_transparent(x, y))
+            }
+        }
+    }
+
+    /// Returns whether the cells between `from` and `to` (both excluded) on the line from `from`
+    /// are transparent.
+    fn line_is_clear(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        Line::new(from, to)
+            .take_while(|&cell| cell != to)
+            .all(|(x, y)| self.is_transparent(x, y))
+    }
+
+    /// Returns the cells seen when looking from `from` towards `to`, e.g. the flight of an arrow.
+    ///
+    /// The path doesn't include `from`. It ends at `to` if `has_los(from, to)`, or at the first
+    /// cell blocking the sight otherwise.
+    pub fn visible_path(&self, from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+        if from != to && !self.line_is_clear(from, to) && self.line_is_clear(to, from) {
+            // Only the line from the other end is clear: follow it backwards
+            let mut path: Vec<(i32, i32)> = Line::new(to, from).filter(|&cell| cell != from)
+                .collect();
+            path.reverse();
+            path.push(to);
+            return path;
+        }
+        let mut path = vec![];
+        for (x, y) in Line::new(from, to) {
+            path.push((x, y));
+            if (x, y) == to || !self.is_transparent(x, y) {
+                break;
+            }
+        }
+        path
+    }
+
     pub fn is_in_fov(&self, x: i32, y: i32) -> bool {
         assert!(x >= 0 && y >= 0);
         let (width, height) = self.size();
@@ -68,6 +235,57 @@ impl Map {
             ffi::TCOD_map_is_walkable(self.tcod_map, x, y) != 0
         }
     }
+
+    pub fn is_transparent(&self, x: i32, y: i32) -> bool {
+        assert!(x >= 0 && y >= 0);
+        let (width, height) = self.size();
+        assert!(x < width && y < height);
+        unsafe {
+            ffi::TCOD_map_is_transparent(self.tcod_map, x, y) != 0
+        }
+    }
+
+    /// Marks a cell as visible or not, e.g. to reveal a lit room. The next `compute_fov`
+    /// overwrites it.
+    pub fn set_in_fov(&mut self, x: i32, y: i32, fov: bool) {
+        assert!(x >= 0 && y >= 0);
+        let (width, height) = self.size();
+        assert!(x < width && y < height);
+        unsafe {
+            ffi::TCOD_map_set_in_fov(self.tcod_map, x, y, fov as c_bool);
+        }
+    }
+
+    /// Returns the number of cells (width * height).
+    pub fn cell_count(&self) -> usize {
+        unsafe {
+            ffi::TCOD_map_get_nb_cells(self.tcod_map) as usize
+        }
+    }
+
+    /// Returns the properties of a cell.
+    pub fn cell(&self, x: i32, y: i32) -> CellProperties {
+        CellProperties {
+            transparent: self.is_transparent(x, y),
+            walkable: self.is_walkable(x, y),
+            in_fov: self.is_in_fov(x, y),
+        }
+    }
+
+    /// Iterates over all the cells, row by row.
+    pub fn cells(&self) -> Cells<'_> {
+        Cells::new(self, |_| true)
+    }
+
+    /// Iterates over the cells in the field of view computed last.
+    pub fn visible_cells(&self) -> Cells<'_> {
+        Cells::new(self, |cell| cell.in_fov)
+    }
+
+    /// Iterates over the walkable cells.
+    pub fn walkable_cells(&self) -> Cells<'_> {
+        Cells::new(self, |cell| cell.walkable)
+    }
     
     pub fn clear(&mut self, transparent: bool, walkable: bool) {
         unsafe {
@@ -76,6 +294,45 @@ impl Map {
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct CellProperties {
+    pub transparent: bool,
+    pub walkable: bool,
+    pub in_fov: bool,
+}
+
+/// An iterator over the `((x, y), properties)` of a `Map`'s cells
+pub struct Cells<'a> {
+    map: &'a Map,
+    width: i32,
+    height: i32,
+    index: i32,
+    filter: fn(&CellProperties) -> bool,
+}
+
+impl<'a> Cells<'a> {
+    fn new(map: &'a Map, filter: fn(&CellProperties) -> bool) -> Cells<'a> {
+        let (width, height) = map.size();
+        Cells { map, width, height, index: 0, filter }
+    }
+}
+
+impl<'a> Iterator for Cells<'a> {
+    type Item = ((i32, i32), CellProperties);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.width * self.height {
+            let (x, y) = (self.index % self.width, self.index / self.width);
+            self.index += 1;
+            let cell = self.map.cell(x, y);
+            if (self.filter)(&cell) {
+                return Some(((x, y), cell));
+            }
+        }
+        None
+    }
+}
+
 impl Clone for Map {
     fn clone(&self) -> Self {
         let (width, height) = self.size();
@@ -101,6 +358,131 @@ impl Drop for Map {
 // ! This is primary so that Map can be used as specs resources
 unsafe impl Send for Map {}
 
+#[derive(Copy, Clone, Debug)]
+struct Symbol {
+    transparent: bool,
+    walkable: bool,
+    marker: bool,
+}
+
+/// The cells the characters stand for in `Map::from_str` and `Map::from_lines`
+///
+/// The default legend has `#` for walls and `.` for floors, with the `@`, `<` and `>` markers on
+/// floor cells.
+#[derive(Clone, Debug)]
+pub struct MapLegend {
+    symbols: HashMap<char, Symbol>,
+}
+
+impl MapLegend {
+    /// Creates a legend without any character.
+    pub fn new() -> MapLegend {
+        MapLegend { symbols: HashMap::new() }
+    }
+
+    /// Makes `ch` stand for a cell with the given properties, e.g. `+` for a closed door
+    /// (walkable but not transparent).
+    pub fn cell(&mut self, ch: char, transparent: bool, walkable: bool) -> &mut MapLegend {
+        self.symbols.insert(ch, Symbol { transparent, walkable, marker: false });
+        self
+    }
+
+    /// Like `cell`, also recording the positions of `ch` in the returned `Markers`.
+    pub fn marker(&mut self, ch: char, transparent: bool, walkable: bool) -> &mut MapLegend {
+        self.symbols.insert(ch, Symbol { transparent, walkable, marker: true });
+        self
+    }
+}
+
+impl Default for MapLegend {
+    fn default() -> MapLegend {
+        let mut legend = MapLegend::new();
+        legend.cell('#', false, false)
+            .cell('.', true, true)
+            .marker('@', true, true)
+            .marker('<', true, true)
+            .marker('>', true, true);
+        legend
+    }
+}
+
+/// The positions of the markers found by `Map::from_str` and `Map::from_lines`, in reading order
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Markers {
+    positions: Vec<(char, (i32, i32))>,
+}
+
+impl Markers {
+    /// Returns the position of the first `marker`.
+    pub fn get(&self, marker: char) -> Option<(i32, i32)> {
+        self.all(marker).next()
+    }
+
+    /// Returns the positions of all the `marker`s.
+    pub fn all(&self, marker: char) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.positions.iter()
+            .filter(move |&&(ch, _)| ch == marker)
+            .map(|&(_, position)| position)
+    }
+
+    /// Iterates over all the markers and their positions.
+    pub fn iter(&self) -> slice::Iter<'_, (char, (i32, i32))> {
+        self.positions.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+/// An error returned by `Map::from_str` and `Map::from_lines`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseMapError {
+    /// There are no rows, or the first one is empty
+    Empty,
+    /// A row doesn't have the same width as the first one
+    UnevenRow { row: i32, width: i32, expected: i32 },
+    /// A character isn't in the legend
+    UnknownCharacter { ch: char, x: i32, y: i32 },
+}
+
+impl fmt::Display for ParseMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseMapError::Empty => write!(f, "Empty map"),
+            ParseMapError::UnevenRow { row, width, expected } => {
+                write!(f, "Row {} is {} cells wide instead of {}", row, width, expected)
+            }
+            ParseMapError::UnknownCharacter { ch, x, y } => {
+                write!(f, "Unknown character {:?} at {}, {}", ch, x, y)
+            }
+        }
+    }
+}
+
+impl error::Error for ParseMapError {}
+
+/// How `Map::has_los_with` traces the sight between two cells
+///
+/// Only `Shadowcasting` matches a field of view: the one of `Map::compute_fov_symmetric`. The
+/// Bresenham lines don't match any `FovAlgorithm`, not even `Basic`, which casts its rays to the
+/// edges of the map rather than to each cell.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LineOfSight {
+    /// libtcod's Bresenham line (`line::Line`) from the viewer to the target. It isn't symmetric.
+    Bresenham,
+    /// The Bresenham lines in both directions: the sight is clear when either of them is.
+    SymmetricBresenham,
+    /// Exactly the result of `Map::compute_fov_symmetric` with lit walls, see
+    /// `fov::is_visible_symmetric`. Slower than the lines, but only the cells in the rectangle
+    /// between the two cells are looked at.
+    Shadowcasting,
+}
+
 #[repr(u32)]
 #[derive(Copy, Clone, Debug)]
 pub enum FovAlgorithm {
@@ -119,3 +501,125 @@ pub enum FovAlgorithm {
     Restrictive = ffi::TCOD_fov_algorithm_t::FOV_RESTRICTIVE as u32,
 }
 native_enum_convert!(FovAlgorithm, TCOD_fov_algorithm_t);
+
+#[cfg(test)]
+mod test {
+    use super::{CellProperties, FovAlgorithm, LineOfSight, Map, MapLegend, ParseMapError};
+
+    fn pillar_room() -> Map {
+        let mut map = Map::new(7, 3);
+        map.clear(true, true);
+        map.set(3, 1, false, false);
+        map
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let map = pillar_room();
+        for &mode in &[LineOfSight::Bresenham, LineOfSight::SymmetricBresenham,
+                       LineOfSight::Shadowcasting] {
+            assert!(!map.has_los_with((1, 1), (5, 1), mode), "{:?}", mode);
+            assert!(map.has_los_with((1, 0), (5, 0), mode), "{:?}", mode);
+            // The wall itself is visible
+            assert!(map.has_los_with((1, 1), (3, 1), mode), "{:?}", mode);
+            assert!(map.has_los_with((1, 1), (1, 1), mode), "{:?}", mode);
+        }
+        assert_eq!(map.has_los((0, 0), (6, 2)), map.has_los((6, 2), (0, 0)));
+    }
+
+    #[test]
+    fn visible_paths() {
+        let map = pillar_room();
+        assert_eq!(map.visible_path((1, 1), (5, 1)), [(2, 1), (3, 1)]);
+        assert_eq!(map.visible_path((1, 0), (4, 0)), [(2, 0), (3, 0), (4, 0)]);
+        assert!(map.visible_path((2, 2), (2, 2)).is_empty());
+    }
+
+    #[test]
+    fn cell_queries() {
+        let mut map = Map::new(3, 2);
+        map.clear(true, true);
+        map.set(1, 0, false, true);
+        map.set(2, 1, true, false);
+        assert_eq!(map.cell_count(), 6);
+        assert!(!map.is_transparent(1, 0) && map.is_walkable(1, 0));
+        assert!(map.is_transparent(2, 1) && !map.is_walkable(2, 1));
+        assert_eq!(map.cell(1, 0),
+                   CellProperties { transparent: false, walkable: true, in_fov: false });
+
+        map.set_in_fov(2, 0, true);
+        assert!(map.is_in_fov(2, 0));
+        let positions = |cells: super::Cells| cells.map(|(pos, _)| pos).collect::<Vec<_>>();
+        assert_eq!(positions(map.cells()), [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+        assert_eq!(positions(map.visible_cells()), [(2, 0)]);
+        assert_eq!(positions(map.walkable_cells()), [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1)]);
+
+        // Computing the field of view replaces the cells set by hand
+        map.compute_fov(0, 1, 1, true, FovAlgorithm::Basic);
+        assert!(!map.is_in_fov(2, 0));
+        assert!(map.is_in_fov(0, 1));
+        assert!(map.visible_cells().all(|((x, _), _)| x < 2));
+    }
+
+    #[test]
+    fn fov_cones() {
+        use std::f32::consts::FRAC_PI_4;
+        use fov::is_in_cone;
+
+        let mut map = Map::new(11, 5);
+        map.clear(true, true);
+        map.set(8, 2, false, false);
+        for &algo in &[FovAlgorithm::Basic, FovAlgorithm::Shadow] {
+            for &light_walls in &[false, true] {
+                let case = format!("{:?}, light_walls: {}", algo, light_walls);
+                map.compute_fov_cone((4, 2), 0.0, FRAC_PI_4, 0, light_walls, algo);
+                assert!(map.is_in_fov(4, 2), "{}", case);
+                // Behind the origin and outside the cone
+                assert!(!map.is_in_fov(3, 2) && !map.is_in_fov(0, 0), "{}", case);
+                assert!(!map.is_in_fov(5, 0) && !map.is_in_fov(4, 4), "{}", case);
+                // In the cone, in front of the wall, at the wall and behind it
+                assert!(map.is_in_fov(7, 2) && map.is_in_fov(6, 0), "{}", case);
+                assert_eq!(map.is_in_fov(8, 2), light_walls, "{}", case);
+                assert!(!map.is_in_fov(9, 2) && map.is_in_fov(9, 1), "{}", case);
+                assert!(map.visible_cells()
+                            .all(|((x, y), _)| is_in_cone(x - 4, y - 2, 0.0, FRAC_PI_4)),
+                        "{}", case);
+                assert_eq!(map.visible_cells().count(), if light_walls { 27 } else { 26 },
+                           "{}", case);
+            }
+        }
+    }
+
+    #[test]
+    fn from_str() {
+        let (map, markers) = Map::from_str("
+#####
+#@.>#
+#.#.#
+#####
+", &MapLegend::default()).unwrap();
+        assert_eq!(map.size(), (5, 4));
+        assert!(map.is_walkable(2, 1) && map.is_transparent(2, 1));
+        assert!(!map.is_walkable(2, 2) && !map.is_transparent(2, 2));
+        assert_eq!(markers.get('@'), Some((1, 1)));
+        assert_eq!(markers.get('>'), Some((3, 1)));
+        assert_eq!(markers.get('<'), None);
+        assert_eq!(markers.len(), 2);
+
+        let mut legend = MapLegend::default();
+        legend.cell('+', false, true).marker('g', true, true);
+        let (map, markers) = Map::from_lines(&["g+g", "..g"], &legend).unwrap();
+        assert!(map.is_walkable(1, 0) && !map.is_transparent(1, 0));
+        assert_eq!(markers.all('g').collect::<Vec<_>>(), [(0, 0), (2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let legend = MapLegend::default();
+        assert_eq!(Map::from_str("\n  \n", &legend).err(), Some(ParseMapError::Empty));
+        assert_eq!(Map::from_lines(&["###", "#.", "###"], &legend).err(),
+                   Some(ParseMapError::UnevenRow { row: 1, width: 2, expected: 3 }));
+        assert_eq!(Map::from_str("#.#\n#?#", &legend).err(),
+                   Some(ParseMapError::UnknownCharacter { ch: '?', x: 1, y: 1 }));
+    }
+}
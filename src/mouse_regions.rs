@@ -0,0 +1,340 @@
+//! Named screen regions reacting to the mouse
+//!
+//! [MouseRegions](./struct.MouseRegions.html) keeps a list of rectangles in console cells
+//! (buttons, inventory slots, the map area, ...) and turns the mouse states into
+//! [RegionEvent](./enum.RegionEvent.html)s: the pointer entering, leaving and hovering over a
+//! region, clicks, double-clicks and drags.
+//!
+//! ```no_run
+//! use tcod::input::{self, Event, MouseButton};
+//! use tcod::mouse_regions::{MouseRegions, RegionEvent};
+//!
+//! let mut regions = MouseRegions::new();
+//! regions.add("map", 0, 0, 60, 50);
+//! regions.add("ok", 65, 45, 10, 3);
+//!
+//! for (_, event) in input::events() {
+//!     if let Event::Mouse(mouse) = event {
+//!         for region_event in regions.update(&mouse) {
+//!             match region_event {
+//!                 RegionEvent::Click { id: "ok", button: MouseButton::Left, .. } => {},
+//!                 RegionEvent::Hover { id: "map", x, y } => { let _ = (x, y); },
+//!                 _ => {},
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use input::{Mouse, MouseButton};
+
+/// What happened to a region, as produced by `MouseRegions::update`
+///
+/// All the positions are in console cells.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RegionEvent<Id> {
+    /// The pointer moved into the region
+    Enter { id: Id },
+    /// The pointer moved out of the region
+    Leave { id: Id },
+    /// The pointer is over the region. Sent for every mouse state passed to `update`.
+    Hover { id: Id, x: i32, y: i32 },
+    /// A button was pressed and released over the region without dragging
+    Click { id: Id, button: MouseButton, x: i32, y: i32 },
+    /// A second click on the same region within the double-click time. It's sent after the
+    /// `Click` event of the second click.
+    DoubleClick { id: Id, button: MouseButton, x: i32, y: i32 },
+    /// The pointer moved to a different cell while a button pressed over the region was held
+    DragStart { id: Id, button: MouseButton, x: i32, y: i32 },
+    /// The pointer moved during a drag. `from` is where the drag started.
+    Drag { id: Id, button: MouseButton, from: (i32, i32), to: (i32, i32) },
+    /// The button was released, ending the drag. `target` is the region under the pointer.
+    Drop { id: Id, button: MouseButton, from: (i32, i32), to: (i32, i32), target: Option<Id> },
+}
+
+#[derive(Clone, Debug)]
+struct Region<Id> {
+    id: Id,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl<Id> Region<Id> {
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Press<Id> {
+    id: Id,
+    button: MouseButton,
+    from: (i32, i32),
+    last: (i32, i32),
+    dragging: bool,
+}
+
+#[derive(Clone, Debug)]
+struct LastClick<Id> {
+    id: Id,
+    button: MouseButton,
+    time: Instant,
+}
+
+/// Rectangular regions tracking the mouse
+///
+/// Regions added later are on top of the earlier ones, so where they overlap, the events go to
+/// the last added region.
+#[derive(Clone, Debug)]
+pub struct MouseRegions<Id> {
+    regions: Vec<Region<Id>>,
+    hovered: Option<Id>,
+    buttons_down: [bool; 3],
+    press: Option<Press<Id>>,
+    last_click: Option<LastClick<Id>>,
+    double_click_time: Duration,
+}
+
+const BUTTONS: [MouseButton; 3] = [MouseButton::Left, MouseButton::Middle, MouseButton::Right];
+
+impl<Id: Clone + PartialEq> Default for MouseRegions<Id> {
+    fn default() -> Self {
+        MouseRegions::new()
+    }
+}
+
+impl<Id: Clone + PartialEq> MouseRegions<Id> {
+    pub fn new() -> Self {
+        MouseRegions {
+            regions: Vec::new(),
+            hovered: None,
+            buttons_down: [false; 3],
+            press: None,
+            last_click: None,
+            double_click_time: Duration::from_millis(400),
+        }
+    }
+
+    /// Adds a region on top of the existing ones. A region with the same `id` is replaced.
+    pub fn add(&mut self, id: Id, x: i32, y: i32, width: i32, height: i32) {
+        assert!(width >= 0 && height >= 0);
+        self.remove(&id);
+        self.regions.push(Region { id, x, y, width, height });
+    }
+
+    /// Removes a region. Returns false if there was no region with that `id`.
+    pub fn remove(&mut self, id: &Id) -> bool {
+        let count = self.regions.len();
+        self.regions.retain(|region| region.id != *id);
+        self.regions.len() != count
+    }
+
+    /// Removes all the regions.
+    pub fn clear(&mut self) {
+        self.regions.clear();
+        self.hovered = None;
+        self.press = None;
+        self.last_click = None;
+    }
+
+    /// Sets the longest time between two clicks of a double-click. The default is 400 ms.
+    pub fn set_double_click_time(&mut self, time: Duration) {
+        self.double_click_time = time;
+    }
+
+    /// Returns the topmost region containing a cell.
+    pub fn region_at(&self, x: i32, y: i32) -> Option<&Id> {
+        self.regions.iter().rev().find(|region| region.contains(x, y)).map(|region| &region.id)
+    }
+
+    /// Returns the region under the pointer.
+    pub fn hovered(&self) -> Option<&Id> {
+        self.hovered.as_ref()
+    }
+
+    /// Processes a mouse state (from a mouse event or `input::mouse_state`) and returns the
+    /// resulting events.
+    pub fn update(&mut self, mouse: &Mouse) -> Vec<RegionEvent<Id>> {
+        self.update_at(mouse, Instant::now())
+    }
+
+    /// Like `update`, with the time the mouse state was read at.
+    pub fn update_at(&mut self, mouse: &Mouse, now: Instant) -> Vec<RegionEvent<Id>> {
+        let mut events = vec![];
+        let (x, y) = (mouse.cx as i32, mouse.cy as i32);
+        let under = self.region_at(x, y).cloned();
+
+        if under != self.hovered {
+            if let Some(id) = self.hovered.take() {
+                events.push(RegionEvent::Leave { id });
+            }
+            if let Some(ref id) = under {
+                events.push(RegionEvent::Enter { id: id.clone() });
+            }
+            self.hovered = under.clone();
+        }
+        if let Some(ref id) = under {
+            events.push(RegionEvent::Hover { id: id.clone(), x, y });
+        }
+
+        // Continue a drag
+        if let Some(ref mut press) = self.press {
+            if (x, y) != press.last {
+                if !press.dragging {
+                    press.dragging = true;
+                    events.push(RegionEvent::DragStart {
+                        id: press.id.clone(),
+                        button: press.button,
+                        x: press.from.0,
+                        y: press.from.1,
+                    });
+                }
+                press.last = (x, y);
+                events.push(RegionEvent::Drag {
+                    id: press.id.clone(),
+                    button: press.button,
+                    from: press.from,
+                    to: (x, y),
+                });
+            }
+        }
+
+        let clicked = MouseButton::pressed(mouse);
+        for (index, &button) in BUTTONS.iter().enumerate() {
+            let down = button.is_down(mouse);
+            let was_down = self.buttons_down[index];
+            self.buttons_down[index] = down;
+            if down && !was_down {
+                if let (None, Some(id)) = (self.press.as_ref(), under.clone()) {
+                    self.press = Some(Press { id, button, from: (x, y), last: (x, y),
+                                              dragging: false });
+                }
+            } else if !down && was_down {
+                let released = matches!(self.press, Some(ref press) if press.button == button);
+                if released {
+                    let press = self.press.take().unwrap();
+                    self.release(press, under.clone(), (x, y), now, &mut events);
+                }
+            } else if !down && clicked.contains(&button) && self.press.is_none() {
+                // libtcod merges the events of one poll, so a quick click may only show up as
+                // `*_pressed` without the button ever being down
+                if let Some(id) = under.clone() {
+                    let press = Press { id, button, from: (x, y), last: (x, y), dragging: false };
+                    self.release(press, under.clone(), (x, y), now, &mut events);
+                }
+            }
+        }
+        events
+    }
+
+    fn release(&mut self, press: Press<Id>, under: Option<Id>, (x, y): (i32, i32), now: Instant,
+               events: &mut Vec<RegionEvent<Id>>) {
+        if press.dragging {
+            events.push(RegionEvent::Drop {
+                id: press.id,
+                button: press.button,
+                from: press.from,
+                to: (x, y),
+                target: under,
+            });
+            return;
+        }
+        if under.as_ref() != Some(&press.id) {
+            return;
+        }
+        events.push(RegionEvent::Click { id: press.id.clone(), button: press.button, x, y });
+        let double = match self.last_click {
+            Some(ref last) => {
+                last.id == press.id && last.button == press.button &&
+                    now.duration_since(last.time) <= self.double_click_time
+            }
+            None => false,
+        };
+        if double {
+            self.last_click = None;
+            events.push(RegionEvent::DoubleClick { id: press.id, button: press.button, x, y });
+        } else {
+            self.last_click = Some(LastClick { id: press.id, button: press.button, time: now });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MouseRegions, RegionEvent};
+    use input::{Mouse, MouseButton};
+    use std::time::{Duration, Instant};
+
+    fn mouse(cx: isize, cy: isize, lbutton: bool) -> Mouse {
+        Mouse { cx, cy, lbutton, ..Default::default() }
+    }
+
+    #[test]
+    fn enter_leave_hover() {
+        let mut regions = MouseRegions::new();
+        regions.add("a", 0, 0, 5, 5);
+        regions.add("b", 4, 4, 5, 5);
+        assert_eq!(regions.region_at(4, 4), Some(&"b"));
+
+        assert_eq!(regions.update(&mouse(1, 1, false)),
+                   vec![RegionEvent::Enter { id: "a" }, RegionEvent::Hover { id: "a", x: 1, y: 1 }]);
+        assert_eq!(regions.update(&mouse(4, 4, false)),
+                   vec![RegionEvent::Leave { id: "a" }, RegionEvent::Enter { id: "b" },
+                        RegionEvent::Hover { id: "b", x: 4, y: 4 }]);
+        assert_eq!(regions.update(&mouse(20, 20, false)), vec![RegionEvent::Leave { id: "b" }]);
+        assert_eq!(regions.hovered(), None);
+    }
+
+    #[test]
+    fn clicks() {
+        let mut regions = MouseRegions::new();
+        regions.add(1, 0, 0, 5, 5);
+        let start = Instant::now();
+        let click = |regions: &mut MouseRegions<i32>, millis| {
+            let now = start + Duration::from_millis(millis);
+            regions.update_at(&mouse(2, 2, true), now);
+            regions.update_at(&mouse(2, 2, false), now)
+        };
+        let single = RegionEvent::Click { id: 1, button: MouseButton::Left, x: 2, y: 2 };
+        let double = RegionEvent::DoubleClick { id: 1, button: MouseButton::Left, x: 2, y: 2 };
+
+        assert!(click(&mut regions, 0).contains(&single));
+        let events = click(&mut regions, 200);
+        assert!(events.contains(&single) && events.contains(&double));
+        assert!(!click(&mut regions, 400).contains(&double));
+        assert!(!click(&mut regions, 1000).contains(&double));
+
+        // Clicks merged into a single poll only have `lbutton_pressed` set
+        let quick = Mouse { cx: 2, cy: 2, lbutton_pressed: true, ..Default::default() };
+        let events = regions.update_at(&quick, start + Duration::from_millis(2000));
+        assert!(events.contains(&single) && !events.contains(&double));
+        let events = regions.update_at(&quick, start + Duration::from_millis(2100));
+        assert!(events.contains(&single) && events.contains(&double));
+        let right = Mouse { cx: 2, cy: 2, rbutton_pressed: true, ..Default::default() };
+        assert!(regions.update(&right).contains(&RegionEvent::Click {
+            id: 1, button: MouseButton::Right, x: 2, y: 2,
+        }));
+    }
+
+    #[test]
+    fn drag_and_drop() {
+        let mut regions = MouseRegions::new();
+        regions.add("slot1", 0, 0, 2, 2);
+        regions.add("slot2", 5, 0, 2, 2);
+        regions.update(&mouse(1, 1, true));
+        let events = regions.update(&mouse(3, 1, true));
+        assert!(events.contains(&RegionEvent::DragStart { id: "slot1", button: MouseButton::Left,
+                                                          x: 1, y: 1 }));
+        assert!(events.contains(&RegionEvent::Drag { id: "slot1", button: MouseButton::Left,
+                                                     from: (1, 1), to: (3, 1) }));
+        let events = regions.update(&mouse(5, 1, false));
+        assert!(events.contains(&RegionEvent::Drop { id: "slot1", button: MouseButton::Left,
+                                                     from: (1, 1), to: (5, 1),
+                                                     target: Some("slot2") }));
+        assert!(!events.iter().any(|e| matches!(*e, RegionEvent::Click { .. })));
+    }
+}
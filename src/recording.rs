@@ -0,0 +1,425 @@
+//! Recording console frames into animated GIF or APNG files
+//!
+//! This module is only available with the `recording` feature.
+//!
+//! A [Recorder](./struct.Recorder.html) captures the contents of any `Console` (an `Offscreen`
+//! console or the `Root` console after `flush`) and renders them with the glyphs of a bitmap font
+//! when the animation is saved. It doesn't need a window, so it works headless as well.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use tcod::console::{Console, FontLayout, Offscreen};
+//! use tcod::recording::Recorder;
+//!
+//! let mut con = Offscreen::new(20, 10);
+//! let mut recorder = Recorder::new("terminal.png", FontLayout::AsciiInCol).unwrap();
+//! for x in 0..20 {
+//!     con.clear();
+//!     con.set_char(x, 5, '@');
+//!     recorder.capture(&con);
+//! }
+//! recorder.save_gif("walk.gif").unwrap();
+//! ```
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind};
+use std::path::Path;
+use std::time::Duration;
+
+use gif;
+use png;
+
+use bindings::ffi;
+use bindings::FromNative;
+use colors::Color;
+use console::{Cell, Console, FontLayout};
+use image::Image;
+use system;
+
+/// The frame delay used when neither `set_frame_delay` nor `system::get_fps` provide one.
+const DEFAULT_FRAME_DELAY_MS: u64 = 100;
+
+/// Converts an ASCII code to its position in a `FontLayout::Tcod` font (copied from libtcod).
+static ASCII_TO_TCOD: [u8; 256] = [
+      0,   0,   0,   0,   0,   0,   0,   0,   0,  76,  77,   0,   0,   0,   0,   0,
+     71,  70,  72,   0,   0,   0,   0,   0,  64,  65,  67,  66,   0,  73,  68,  69,
+      0,   1,   2,   3,   4,   5,   6,   7,   8,   9,  10,  11,  12,  13,  14,  15,
+     16,  17,  18,  19,  20,  21,  22,  23,  24,  25,  26,  27,  28,  29,  30,  31,
+     32,  96,  97,  98,  99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110,
+    111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121,  33,  34,  35,  36,  37,
+     38, 128, 129, 130, 131, 132, 133, 134, 135, 136, 137, 138, 139, 140, 141, 142,
+    143, 144, 145, 146, 147, 148, 149, 150, 151, 152, 153,  39,  40,  41,  42,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+     43,  44,  45,  46,  49,   0,   0,   0,   0,  81,  78,  87,  88,   0,   0,  55,
+     53,  50,  52,  51,  47,  48,   0,   0,  85,  86,  82,  84,  83,  79,  80,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,   0,  56,  54,   0,   0,   0,   0,   0,
+     74,  75,  57,  58,  59,  60,  61,  62,  63,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+/// The glyphs of a bitmap font, stored as the glyph coverage (0-255) of every pixel
+struct Font {
+    layout: FontLayout,
+    chars_horizontal: i32,
+    chars_vertical: i32,
+    glyph_width: i32,
+    glyph_height: i32,
+    image_width: i32,
+    coverage: Vec<u8>,
+}
+
+impl Font {
+    fn load(path: &Path, layout: FontLayout,
+            chars_horizontal: i32, chars_vertical: i32) -> Result<Font, Error> {
+        assert!(chars_horizontal > 0 && chars_vertical > 0);
+        let image = Image::from_file(path)?;
+        let (width, height) = image.get_size();
+        if width < chars_horizontal || height < chars_vertical {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "The font image is smaller than its number of characters"));
+        }
+
+        // Like libtcod, treat the color of the top left pixel as the font's background.
+        let key_color = image.get_pixel(0, 0);
+        let mut coverage = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = image.get_pixel(x, y);
+                let alpha = image.get_alpha(x, y) as u32;
+                let value = if pixel == key_color && alpha == 255 {
+                    0
+                } else {
+                    let luminance = (pixel.r as u32 * 299 + pixel.g as u32 * 587 +
+                                     pixel.b as u32 * 114) / 1000;
+                    luminance * alpha / 255
+                };
+                coverage.push(value as u8);
+            }
+        }
+
+        Ok(Font {
+            layout,
+            chars_horizontal,
+            chars_vertical,
+            glyph_width: width / chars_horizontal,
+            glyph_height: height / chars_vertical,
+            image_width: width,
+            coverage,
+        })
+    }
+
+    /// Returns the column and row of `glyph` in the font image.
+    fn glyph_position(&self, glyph: char) -> Option<(i32, i32)> {
+        let code = glyph as u32;
+        if code > 255 {
+            return None;
+        }
+        let (x, y) = match self.layout {
+            FontLayout::AsciiInCol => (code as i32 / self.chars_vertical,
+                                       code as i32 % self.chars_vertical),
+            FontLayout::AsciiInRow => (code as i32 % self.chars_horizontal,
+                                       code as i32 / self.chars_horizontal),
+            FontLayout::Tcod => {
+                let index = ASCII_TO_TCOD[code as usize] as i32;
+                (index % self.chars_horizontal, index / self.chars_horizontal)
+            }
+        };
+        if x < self.chars_horizontal && y < self.chars_vertical {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Renders the cells of a console into RGB pixels.
+    fn render(&self, frame: &Frame) -> Vec<u8> {
+        let pixel_width = (frame.width * self.glyph_width) as usize;
+        let image_width = self.image_width as usize;
+        let mut pixels = vec![0; pixel_width * (frame.height * self.glyph_height) as usize * 3];
+        for (i, cell) in frame.cells.iter().enumerate() {
+            let (cx, cy) = (i as i32 % frame.width, i as i32 / frame.width);
+            let glyph = self.glyph_position(cell.glyph);
+            for gy in 0..self.glyph_height {
+                for gx in 0..self.glyph_width {
+                    let coverage = match glyph {
+                        Some((fx, fy)) => {
+                            let x = (fx * self.glyph_width + gx) as usize;
+                            let y = (fy * self.glyph_height + gy) as usize;
+                            self.coverage[y * image_width + x]
+                        }
+                        None => 0,
+                    };
+                    let color = blend(cell.background, cell.foreground, coverage);
+                    let x = (cx * self.glyph_width + gx) as usize;
+                    let y = (cy * self.glyph_height + gy) as usize;
+                    let offset = (y * pixel_width + x) * 3;
+                    pixels[offset] = color.r;
+                    pixels[offset + 1] = color.g;
+                    pixels[offset + 2] = color.b;
+                }
+            }
+        }
+        pixels
+    }
+}
+
+#[inline]
+fn blend(background: Color, foreground: Color, coverage: u8) -> Color {
+    let mix = |b: u8, f: u8| {
+        ((b as u32 * (255 - coverage as u32) + f as u32 * coverage as u32) / 255) as u8
+    };
+    Color {
+        r: mix(background.r, foreground.r),
+        g: mix(background.g, foreground.g),
+        b: mix(background.b, foreground.b),
+    }
+}
+
+/// Reads the cells of `console` like `Console::get_cells`, except that the glyphs above 255,
+/// which have no place in the font, are kept (and drawn as empty cells) instead of panicking.
+fn read_cells<T: Console>(console: &T) -> Vec<Cell> {
+    let con = unsafe { *console.as_native() };
+    let mut cells = Vec::with_capacity((console.width() * console.height()) as usize);
+    for y in 0..console.height() {
+        for x in 0..console.width() {
+            unsafe {
+                let code = ffi::TCOD_console_get_char(con, x, y);
+                cells.push(Cell {
+                    glyph: ::std::char::from_u32(code as u32).unwrap_or(' '),
+                    foreground: FromNative::from_native(
+                        ffi::TCOD_console_get_char_foreground(con, x, y)),
+                    background: FromNative::from_native(
+                        ffi::TCOD_console_get_char_background(con, x, y)),
+                });
+            }
+        }
+    }
+    cells
+}
+
+fn encoding_error<E: Display>(error: E) -> Error {
+    Error::new(ErrorKind::Other, error.to_string())
+}
+
+struct Frame {
+    width: i32,
+    height: i32,
+    cells: Vec<Cell>,
+    delay: Duration,
+}
+
+/// Records console frames and saves them as an animated GIF or APNG
+///
+/// Only the contents of the consoles are stored while recording, the frames are rendered when
+/// the animation is saved. All the captured consoles must have the same size.
+pub struct Recorder {
+    font: Font,
+    frames: Vec<Frame>,
+    frame_delay: Option<Duration>,
+}
+
+impl Recorder {
+    /// Creates a recorder rendering the frames with the given bitmap font.
+    ///
+    /// The font is interpreted the same way as by `RootInitializer::font`, with the default
+    /// number of characters for the layout (16x16 for the ASCII layouts and 32x8 for
+    /// `FontLayout::Tcod`).
+    pub fn new<P>(font_path: P, font_layout: FontLayout) -> Result<Recorder, Error>
+        where P: AsRef<Path>
+    {
+        let (horizontal, vertical) = match font_layout {
+            FontLayout::AsciiInCol | FontLayout::AsciiInRow => (16, 16),
+            FontLayout::Tcod => (32, 8),
+        };
+        Recorder::with_font_dimensions(font_path, font_layout, horizontal, vertical)
+    }
+
+    /// Creates a recorder for a font with a custom number of characters, like
+    /// `RootInitializer::font_dimensions`.
+    pub fn with_font_dimensions<P>(font_path: P, font_layout: FontLayout,
+                                   horizontal: i32, vertical: i32) -> Result<Recorder, Error>
+        where P: AsRef<Path>
+    {
+        let font = Font::load(font_path.as_ref(), font_layout, horizontal, vertical)?;
+        Ok(Recorder { font, frames: Vec::new(), frame_delay: None })
+    }
+
+    /// Sets how long each of the following frames is displayed. With `None` (the default), the
+    /// delay is derived from `system::get_fps` at the time the frame is captured.
+    pub fn set_frame_delay(&mut self, delay: Option<Duration>) {
+        self.frame_delay = delay;
+    }
+
+    /// Stores the current contents of `console` as the next frame.
+    pub fn capture<T>(&mut self, console: &T) where T: Console {
+        let (width, height) = (console.width(), console.height());
+        if let Some(first) = self.frames.first() {
+            assert!(first.width == width && first.height == height,
+                    "all the recorded consoles must have the same size");
+        }
+        let delay = self.frame_delay.unwrap_or_else(|| {
+            match system::get_fps() {
+                0 => Duration::from_millis(DEFAULT_FRAME_DELAY_MS),
+                fps => Duration::from_millis(1000 / fps as u64),
+            }
+        });
+        self.frames.push(Frame {
+            width,
+            height,
+            cells: read_cells(console),
+            delay,
+        });
+    }
+
+    /// Returns the number of captured frames.
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Removes all the captured frames.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Returns the size of the rendered frames in pixels.
+    pub fn pixel_size(&self) -> Option<(u32, u32)> {
+        self.frames.first().map(|frame| {
+            ((frame.width * self.font.glyph_width) as u32,
+             (frame.height * self.font.glyph_height) as u32)
+        })
+    }
+
+    fn checked_pixel_size(&self) -> Result<(u32, u32), Error> {
+        self.pixel_size().ok_or_else(|| {
+            Error::new(ErrorKind::InvalidInput, "No frames have been captured")
+        })
+    }
+
+    /// Saves the captured frames as an animated GIF that loops forever.
+    pub fn save_gif<P>(&self, path: P) -> Result<(), Error> where P: AsRef<Path> {
+        let (width, height) = self.checked_pixel_size()?;
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(Error::new(ErrorKind::InvalidInput, "The frames are too big for a GIF"));
+        }
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = gif::Encoder::new(file, width as u16, height as u16, &[])
+            .map_err(encoding_error)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(encoding_error)?;
+        for frame in &self.frames {
+            let pixels = self.font.render(frame);
+            let mut gif_frame = gif::Frame::from_rgb_speed(width as u16, height as u16,
+                                                           &pixels, 10);
+            // GIF delays are in hundredths of a second
+            let centiseconds = (duration_millis(frame.delay) + 5) / 10;
+            gif_frame.delay = centiseconds.max(1).min(u16::MAX as u64) as u16;
+            encoder.write_frame(&gif_frame).map_err(encoding_error)?;
+        }
+        Ok(())
+    }
+
+    /// Saves the captured frames as an animated PNG that loops forever.
+    pub fn save_apng<P>(&self, path: P) -> Result<(), Error> where P: AsRef<Path> {
+        let (width, height) = self.checked_pixel_size()?;
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, 0).map_err(encoding_error)?;
+        let mut writer = encoder.write_header().map_err(encoding_error)?;
+        for frame in &self.frames {
+            let millis = duration_millis(frame.delay).min(u16::MAX as u64) as u16;
+            writer.set_frame_delay(millis, 1000).map_err(encoding_error)?;
+            writer.write_image_data(&self.font.render(frame)).map_err(encoding_error)?;
+        }
+        writer.finish().map_err(encoding_error)
+    }
+}
+
+fn duration_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + duration.subsec_millis() as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Font, Frame, blend};
+    use colors::Color;
+    use console::{Cell, FontLayout};
+    use std::time::Duration;
+
+    // A 2x2 font of 2x1 pixel glyphs: glyph 0 is empty, 1 is full, 2 has
+    // its left pixel set and 3 its right one.
+    fn test_font(layout: FontLayout) -> Font {
+        Font {
+            layout,
+            chars_horizontal: 2,
+            chars_vertical: 2,
+            glyph_width: 2,
+            glyph_height: 1,
+            image_width: 4,
+            coverage: vec![0, 0, 255, 255,
+                           255, 0, 0, 255],
+        }
+    }
+
+    #[test]
+    fn glyph_positions() {
+        let in_row = test_font(FontLayout::AsciiInRow);
+        assert_eq!(in_row.glyph_position('\u{1}'), Some((1, 0)));
+        assert_eq!(in_row.glyph_position('\u{2}'), Some((0, 1)));
+        assert_eq!(in_row.glyph_position('\u{4}'), None);
+
+        let in_col = test_font(FontLayout::AsciiInCol);
+        assert_eq!(in_col.glyph_position('\u{1}'), Some((0, 1)));
+        assert_eq!(in_col.glyph_position('\u{2}'), Some((1, 0)));
+
+        let tcod = test_font(FontLayout::Tcod);
+        assert_eq!(tcod.glyph_position(' '), Some((0, 0)));
+        assert_eq!(tcod.glyph_position('!'), Some((1, 0)));
+    }
+
+    #[test]
+    fn blend_colors() {
+        let (black, white) = (Color::new(0, 0, 0), Color::new(255, 255, 255));
+        assert_eq!(blend(black, white, 0), black);
+        assert_eq!(blend(black, white, 255), white);
+        assert_eq!(blend(black, white, 51), Color::new(51, 51, 51));
+    }
+
+    #[test]
+    fn render_frame() {
+        let font = test_font(FontLayout::AsciiInRow);
+        let (fg, bg) = (Color::new(255, 0, 0), Color::new(0, 0, 255));
+        let frame = Frame {
+            width: 2,
+            height: 1,
+            cells: vec![Cell::new('\u{2}', fg, bg), Cell::new('\u{3}', fg, bg)],
+            delay: Duration::from_millis(100),
+        };
+        assert_eq!(font.render(&frame), vec![255, 0, 0, 0, 0, 255,
+                                             0, 0, 255, 255, 0, 0]);
+    }
+
+    #[test]
+    fn render_uneven_font() {
+        // The same glyphs in a 5 pixel wide image, whose last column belongs to no glyph
+        let mut font = test_font(FontLayout::AsciiInRow);
+        font.image_width = 5;
+        font.coverage = vec![0, 0, 255, 255, 0,
+                             255, 0, 0, 255, 0];
+        let (fg, bg) = (Color::new(255, 0, 0), Color::new(0, 0, 255));
+        let frame = Frame {
+            width: 3,
+            height: 1,
+            cells: vec![Cell::new('\u{2}', fg, bg), Cell::new('\u{3}', fg, bg),
+                        Cell::new('\u{2500}', fg, bg)],
+            delay: Duration::from_millis(100),
+        };
+        assert_eq!(font.render(&frame), vec![255, 0, 0, 0, 0, 255,
+                                             0, 0, 255, 255, 0, 0,
+                                             0, 0, 255, 0, 0, 255]);
+    }
+}
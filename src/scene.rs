@@ -0,0 +1,298 @@
+//! A stack of screens such as menus, the game and its modal windows
+//!
+//! Every screen is a [Scene](./trait.Scene.html). The [SceneStack](./struct.SceneStack.html) sends
+//! the input and the updates to the scene on top, and scenes switch to other screens by returning
+//! a [Transition](./enum.Transition.html). A scene can let the ones underneath show through, e.g.
+//! an inventory window drawn over the dimmed game:
+//!
+//! ```no_run
+//! use tcod::app::Runner;
+//! use tcod::console::{Console, Root};
+//! use tcod::input::{Event, KeyCode};
+//! use tcod::scene::{Background, Scene, SceneStack, Transition};
+//!
+//! struct Game;
+//! struct Inventory;
+//!
+//! impl Scene for Game {
+//!     fn render(&mut self, root: &mut Root, _alpha: f32) {
+//!         root.clear();
+//!         root.print(1, 1, "The game");
+//!     }
+//!
+//!     fn handle_event(&mut self, event: &Event) -> Transition {
+//!         match *event {
+//!             Event::Key(key) if key.printable == 'i' => Transition::Push(Box::new(Inventory)),
+//!             Event::Key(key) if key.code == KeyCode::Escape => Transition::Quit,
+//!             _ => Transition::None,
+//!         }
+//!     }
+//! }
+//!
+//! impl Scene for Inventory {
+//!     fn render(&mut self, root: &mut Root, _alpha: f32) {
+//!         root.print_frame(20, 10, 40, 30, true, tcod::BackgroundFlag::Set, Some("Inventory"));
+//!     }
+//!
+//!     fn handle_event(&mut self, event: &Event) -> Transition {
+//!         match *event {
+//!             Event::Key(key) if key.code == KeyCode::Escape => Transition::Pop,
+//!             _ => Transition::None,
+//!         }
+//!     }
+//!
+//!     fn background(&self) -> Background {
+//!         Background::Dimmed(0.5)
+//!     }
+//! }
+//!
+//! let mut root = Root::initializer().size(80, 50).init();
+//! let mut scenes = SceneStack::new();
+//! scenes.push(Box::new(Game));
+//! Runner::new(30).run(&mut root, &mut scenes);
+//! ```
+
+use std::time::Duration;
+
+use app::App;
+use console::{self, Console, Offscreen, Root};
+use input::Event;
+
+/// What the stack should do after a scene handled an event or an update
+pub enum Transition {
+    /// Stay on the current scene
+    None,
+    /// Put a new scene on top of the current one
+    Push(Box<dyn Scene>),
+    /// Remove the current scene, going back to the one below
+    Pop,
+    /// Replace the current scene with a new one
+    Replace(Box<dyn Scene>),
+    /// Remove all the scenes, which finishes the stack
+    Quit,
+}
+
+/// How the scenes under a scene are shown
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Background {
+    /// Not at all. The scene covers the whole screen.
+    Hidden,
+    /// The scene is drawn over them
+    Visible,
+    /// The scene is drawn over them after they're darkened by blending in black with the given
+    /// alpha (0.0 leaves them as they are, 1.0 makes them black)
+    Dimmed(f32),
+}
+
+/// A screen on a [SceneStack](./struct.SceneStack.html)
+pub trait Scene {
+    /// Advances the scene by `dt`. Only the scene on top is updated.
+    fn update(&mut self, dt: Duration) -> Transition {
+        let _ = dt;
+        Transition::None
+    }
+
+    /// Draws the scene. Scenes shown under this one have already been drawn.
+    ///
+    /// `alpha` is passed on from `App::render`.
+    fn render(&mut self, root: &mut Root, alpha: f32);
+
+    /// Handles an input event. Only the scene on top receives events.
+    fn handle_event(&mut self, event: &Event) -> Transition {
+        let _ = event;
+        Transition::None
+    }
+
+    /// Returns how the scenes under this one are shown. The default is `Background::Hidden`.
+    fn background(&self) -> Background {
+        Background::Hidden
+    }
+
+    /// Called when the scene gets back on top after the scene above it was removed.
+    fn resume(&mut self) {}
+}
+
+/// A stack of scenes, itself usable as an [App](../app/trait.App.html)
+#[derive(Default)]
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    shade: Option<Offscreen>,
+}
+
+impl SceneStack {
+    pub fn new() -> SceneStack {
+        SceneStack::default()
+    }
+
+    /// Puts a scene on top of the stack.
+    pub fn push(&mut self, scene: Box<dyn Scene>) {
+        self.scenes.push(scene);
+    }
+
+    /// Removes the scene on top of the stack and returns it.
+    pub fn pop(&mut self) -> Option<Box<dyn Scene>> {
+        let scene = self.scenes.pop();
+        if let Some(top) = self.scenes.last_mut() {
+            top.resume();
+        }
+        scene
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// Performs a transition returned by the scene on top.
+    pub fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(scene) => self.push(scene),
+            Transition::Pop => {
+                self.pop();
+            }
+            Transition::Replace(scene) => {
+                self.scenes.pop();
+                self.scenes.push(scene);
+            }
+            Transition::Quit => self.scenes.clear(),
+        }
+    }
+
+    /// Returns the index of the lowest scene that's drawn.
+    fn first_visible(&self) -> usize {
+        self.scenes.iter()
+            .rposition(|scene| scene.background() == Background::Hidden)
+            .unwrap_or(0)
+    }
+
+    fn dim(&mut self, root: &mut Root, alpha: f32) {
+        let (width, height) = (root.width(), root.height());
+        let reuse = matches!(self.shade, Some(ref shade)
+                             if shade.width() == width && shade.height() == height);
+        if !reuse {
+            self.shade = Some(Offscreen::new(width, height));
+        }
+        if let Some(ref shade) = self.shade {
+            console::blit(shade, (0, 0), (width, height), root, (0, 0), alpha, alpha);
+        }
+    }
+}
+
+impl App for SceneStack {
+    fn update(&mut self, dt: Duration) {
+        let transition = match self.scenes.last_mut() {
+            Some(top) => top.update(dt),
+            None => return,
+        };
+        self.apply(transition);
+    }
+
+    fn render(&mut self, root: &mut Root, alpha: f32) {
+        for index in self.first_visible()..self.scenes.len() {
+            if index > 0 {
+                if let Background::Dimmed(dim) = self.scenes[index].background() {
+                    self.dim(root, dim);
+                }
+            }
+            self.scenes[index].render(root, alpha);
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        let transition = match self.scenes.last_mut() {
+            Some(top) => top.handle_event(&event),
+            None => return,
+        };
+        self.apply(transition);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.scenes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Background, Scene, SceneStack, Transition};
+    use app::App;
+    use console::Root;
+    use input::{Event, Key};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    struct Logged {
+        name: &'static str,
+        background: Background,
+        log: Rc<RefCell<Vec<String>>>,
+        next: Option<Transition>,
+    }
+
+    impl Logged {
+        fn new(name: &'static str, background: Background,
+               log: &Rc<RefCell<Vec<String>>>) -> Box<Logged> {
+            Box::new(Logged { name, background, log: log.clone(), next: None })
+        }
+    }
+
+    impl Scene for Logged {
+        fn update(&mut self, _dt: Duration) -> Transition {
+            self.log.borrow_mut().push(format!("update {}", self.name));
+            self.next.take().unwrap_or(Transition::None)
+        }
+
+        fn render(&mut self, _root: &mut Root, _alpha: f32) {}
+
+        fn handle_event(&mut self, _event: &Event) -> Transition {
+            self.log.borrow_mut().push(format!("event {}", self.name));
+            Transition::None
+        }
+
+        fn background(&self) -> Background {
+            self.background
+        }
+
+        fn resume(&mut self) {
+            self.log.borrow_mut().push(format!("resume {}", self.name));
+        }
+    }
+
+    #[test]
+    fn transitions() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut stack = SceneStack::new();
+        let mut game = Logged::new("game", Background::Hidden, &log);
+        game.next = Some(Transition::Push(Logged::new("inventory", Background::Dimmed(0.5), &log)));
+        stack.push(game);
+
+        stack.update(Duration::from_millis(10));
+        assert_eq!(stack.len(), 2);
+        stack.handle_event(Event::Key(Key::default()));
+        stack.apply(Transition::Pop);
+        stack.handle_event(Event::Key(Key::default()));
+        assert_eq!(*log.borrow(), ["update game", "event inventory", "resume game", "event game"]);
+
+        stack.apply(Transition::Replace(Logged::new("menu", Background::Hidden, &log)));
+        assert_eq!(stack.len(), 1);
+        stack.apply(Transition::Quit);
+        assert!(stack.is_finished());
+    }
+
+    #[test]
+    fn visible_scenes() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut stack = SceneStack::new();
+        assert_eq!(stack.first_visible(), 0);
+        stack.push(Logged::new("menu", Background::Hidden, &log));
+        stack.push(Logged::new("game", Background::Hidden, &log));
+        stack.push(Logged::new("inventory", Background::Dimmed(0.5), &log));
+        stack.push(Logged::new("tooltip", Background::Visible, &log));
+        assert_eq!(stack.first_visible(), 1);
+        stack.push(Logged::new("options", Background::Hidden, &log));
+        assert_eq!(stack.first_visible(), 4);
+    }
+}
@@ -0,0 +1,244 @@
+//! A single or multi-line text input field
+//!
+//! [TextField](./struct.TextField.html) wraps libtcod's text field toolkit. It handles typing,
+//! cursor movement, selection (with Shift) and the clipboard (Ctrl+C, Ctrl+X and Ctrl+V), so
+//! name entry screens don't have to be written by hand:
+//!
+//! ```no_run
+//! use tcod::console::{Console, Root};
+//! use tcod::text_field::TextField;
+//!
+//! let mut root = Root::initializer().size(80, 50).init();
+//! let mut field = TextField::new(10, 20, 40, 1, 20);
+//! field.set_prompt("Name: ");
+//! field.set_cursor(Some('_'));
+//!
+//! let name = loop {
+//!     root.clear();
+//!     field.render(&mut root);
+//!     root.flush();
+//!     if let Some(name) = field.update(root.wait_for_keypress(true)) {
+//!         break name;
+//!     }
+//! };
+//! ```
+
+use std::ptr;
+use std::time::Duration;
+
+use bindings::ffi;
+use bindings::{AsNative, CStr, CString};
+use colors::Color;
+use console::Console;
+use input::Key;
+
+pub struct TextField {
+    tcod_text: ffi::TCOD_text_t,
+    prompt: Option<CString>,
+    cursor: Option<char>,
+    blink_interval: i32,
+    tab_size: i32,
+}
+
+impl AsNative<ffi::TCOD_text_t> for TextField {
+    unsafe fn as_native(&self) -> &ffi::TCOD_text_t {
+        &self.tcod_text
+    }
+
+    unsafe fn as_native_mut(&mut self) -> &mut ffi::TCOD_text_t {
+        &mut self.tcod_text
+    }
+}
+
+impl Drop for TextField {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::TCOD_text_delete(self.tcod_text);
+        }
+    }
+}
+
+impl TextField {
+    /// Creates a text field occupying a `width` x `height` rectangle at (`x`, `y`) of the
+    /// console it's rendered on. The field is multi-line when `height` is greater than 1.
+    ///
+    /// The text stays shorter than `max_length` characters: once `max_length - 1` have been
+    /// entered, typing replaces the last one. If `max_length` is 0, the size of the field
+    /// (`width * height`) is used instead.
+    pub fn new(x: i32, y: i32, width: i32, height: i32, max_length: i32) -> TextField {
+        assert!(width > 0 && height > 0);
+        assert!(max_length >= 0);
+        let tcod_text = unsafe {
+            ffi::TCOD_text_init(x, y, width, height, max_length)
+        };
+        TextField {
+            tcod_text,
+            prompt: None,
+            cursor: None,
+            blink_interval: 800,
+            tab_size: 0,
+        }
+    }
+
+    /// Moves the field to a different position of the console.
+    pub fn set_position(&mut self, x: i32, y: i32) {
+        unsafe {
+            ffi::TCOD_text_set_pos(self.tcod_text, x, y);
+        }
+    }
+
+    /// Sets the text displayed in front of the entered text.
+    pub fn set_prompt(&mut self, prompt: &str) {
+        self.prompt = if prompt.is_empty() {
+            None
+        } else {
+            Some(CString::new(prompt).unwrap())
+        };
+        self.update_properties();
+    }
+
+    /// Sets the character drawn at the cursor position. With `None` (the default), the cursor is
+    /// shown by swapping the colors of the cell under it.
+    pub fn set_cursor(&mut self, cursor: Option<char>) {
+        self.cursor = cursor;
+        self.update_properties();
+    }
+
+    /// Sets how long a full on/off cycle of the blinking cursor takes. The default is 800 ms.
+    pub fn set_blink_interval(&mut self, interval: Duration) {
+        let millis = interval.as_secs() as i32 * 1000 + interval.subsec_millis() as i32;
+        assert!(millis > 0, "the blink interval must be at least one millisecond");
+        self.blink_interval = millis;
+        self.update_properties();
+    }
+
+    /// Sets how many spaces the Tab key inserts. With 0 (the default), Tab is ignored.
+    pub fn set_tab_size(&mut self, tab_size: i32) {
+        assert!(tab_size >= 0);
+        self.tab_size = tab_size;
+        self.update_properties();
+    }
+
+    fn update_properties(&mut self) {
+        let cursor = self.cursor.map_or(0, |c| c as i32);
+        let prompt = self.prompt.as_ref().map_or(ptr::null(), |p| p.as_ptr());
+        unsafe {
+            ffi::TCOD_text_set_properties(self.tcod_text, cursor, self.blink_interval,
+                                          prompt, self.tab_size);
+        }
+    }
+
+    /// Sets the text and background colors. The background is blended onto the console with
+    /// `background_alpha` (1.0 is fully opaque).
+    pub fn set_colors(&mut self, foreground: Color, background: Color, background_alpha: f32) {
+        unsafe {
+            ffi::TCOD_text_set_colors(self.tcod_text, *foreground.as_native(),
+                                      *background.as_native(), background_alpha);
+        }
+    }
+
+    /// Processes a key event.
+    ///
+    /// Returns the entered text once Enter has been pressed in a single-line field (in a
+    /// multi-line one, Enter starts a new line instead). The field keeps returning the text,
+    /// along with any further edits, until `reset` is called.
+    pub fn update(&mut self, key: Key) -> Option<String> {
+        let editing = unsafe {
+            ffi::TCOD_text_update(self.tcod_text, key.into()) != 0
+        };
+        if editing {
+            None
+        } else {
+            Some(self.text())
+        }
+    }
+
+    /// Draws the field onto a console.
+    pub fn render<T: Console>(&self, console: &mut T) {
+        unsafe {
+            ffi::TCOD_text_render(self.tcod_text, *console.as_native());
+        }
+    }
+
+    /// Returns the text entered so far.
+    pub fn text(&self) -> String {
+        unsafe {
+            CStr::from_ptr(ffi::TCOD_text_get(self.tcod_text)).to_string_lossy().into_owned()
+        }
+    }
+
+    /// Clears the text and starts accepting input again.
+    pub fn reset(&mut self) {
+        unsafe {
+            ffi::TCOD_text_reset(self.tcod_text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TextField;
+    use input::{Key, KeyCode};
+
+    fn press(code: KeyCode, printable: char) -> Key {
+        let mut key = Key::default();
+        key.code = code;
+        key.printable = printable;
+        key.pressed = true;
+        key
+    }
+
+    fn type_text(field: &mut TextField, text: &str) {
+        for c in text.chars() {
+            assert_eq!(field.update(press(KeyCode::Char, c)), None);
+        }
+    }
+
+    #[test]
+    fn editing() {
+        let mut field = TextField::new(0, 0, 20, 1, 0);
+        type_text(&mut field, "helo");
+        assert_eq!(field.text(), "helo");
+
+        // Releases are ignored
+        let mut release = press(KeyCode::Char, 'x');
+        release.pressed = false;
+        field.update(release);
+        assert_eq!(field.text(), "helo");
+
+        // Insert before the cursor after moving it back
+        field.update(press(KeyCode::Left, '\0'));
+        type_text(&mut field, "l");
+        assert_eq!(field.text(), "hello");
+
+        field.update(press(KeyCode::End, '\0'));
+        field.update(press(KeyCode::Backspace, '\0'));
+        field.update(press(KeyCode::Backspace, '\0'));
+        assert_eq!(field.text(), "hel");
+        field.update(press(KeyCode::Home, '\0'));
+        field.update(press(KeyCode::Delete, '\0'));
+        type_text(&mut field, "y");
+        assert_eq!(field.text(), "yel");
+
+        // Backspace at the start does nothing
+        field.update(press(KeyCode::Home, '\0'));
+        field.update(press(KeyCode::Backspace, '\0'));
+        assert_eq!(field.text(), "yel");
+
+        assert_eq!(field.update(press(KeyCode::Enter, '\0')), Some("yel".to_string()));
+        assert_eq!(field.update(press(KeyCode::Char, 'p')), Some("pyel".to_string()));
+        field.reset();
+        assert_eq!(field.text(), "");
+        type_text(&mut field, "p");
+        assert_eq!(field.text(), "p");
+    }
+
+    #[test]
+    fn max_length() {
+        let mut field = TextField::new(0, 0, 20, 1, 5);
+        type_text(&mut field, "abcd");
+        assert_eq!(field.text(), "abcd");
+        type_text(&mut field, "ef");
+        assert_eq!(field.text(), "abcf");
+    }
+}
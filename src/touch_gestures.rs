@@ -0,0 +1,181 @@
+//! Tap and drag gestures on touch screens
+//!
+//! [TouchGestures](./struct.TouchGestures.html) turns the `Event::Touch` events into
+//! [Gesture](./enum.Gesture.html)s in console cells, so a game can be played on a tablet without
+//! relying on the emulated mouse:
+//!
+//! ```no_run
+//! use tcod::input::{self, Event, FINGER, KEY};
+//! use tcod::touch_gestures::{Gesture, TouchGestures};
+//!
+//! // Touches no longer move the mouse, only the gestures handle them
+//! input::mouse_includes_touch(false);
+//! let mut gestures = TouchGestures::new();
+//! while let Some((_, event)) = input::check_for_event(KEY | FINGER) {
+//!     if let Event::Touch(touch) = event {
+//!         for gesture in gestures.update(&touch) {
+//!             match gesture {
+//!                 Gesture::Tap { cx, cy } => { let _ = (cx, cy); /* walk there */ },
+//!                 Gesture::DragEnd { from, to } => { let _ = (from, to); /* throw */ },
+//!                 _ => {},
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Only the first finger of a touch makes gestures: the fingers added while it is on the screen
+//! are ignored.
+
+use std::time::{Duration, Instant};
+
+use input::{Touch, TouchPhase};
+
+/// A gesture recognized by `TouchGestures`, in console cells
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Gesture {
+    /// A finger touched a cell and was lifted quickly without moving to another cell
+    Tap { cx: i32, cy: i32 },
+    /// The finger moved to a different cell than the one it touched first
+    DragStart { cx: i32, cy: i32 },
+    /// The finger moved during a drag. `from` is where the drag started.
+    Drag { from: (i32, i32), to: (i32, i32) },
+    /// The finger was lifted, ending the drag
+    DragEnd { from: (i32, i32), to: (i32, i32) },
+}
+
+#[derive(Clone, Debug)]
+struct TouchTrack {
+    from: (i32, i32),
+    last: (i32, i32),
+    pressed_at: Instant,
+    dragging: bool,
+}
+
+/// Recognizes taps and drags of a single finger
+#[derive(Clone, Debug)]
+pub struct TouchGestures {
+    tap_time: Duration,
+    track: Option<TouchTrack>,
+}
+
+impl Default for TouchGestures {
+    fn default() -> Self {
+        TouchGestures::new()
+    }
+}
+
+impl TouchGestures {
+    pub fn new() -> Self {
+        TouchGestures {
+            tap_time: Duration::from_millis(300),
+            track: None,
+        }
+    }
+
+    /// Sets the longest time a finger can stay on the screen for a tap. The default is 300 ms.
+    pub fn set_tap_time(&mut self, time: Duration) {
+        self.tap_time = time;
+    }
+
+    /// Returns whether a finger is being dragged.
+    pub fn is_dragging(&self) -> bool {
+        matches!(self.track, Some(ref track) if track.dragging)
+    }
+
+    /// Processes a touch event and returns the resulting gestures.
+    pub fn update(&mut self, touch: &Touch) -> Vec<Gesture> {
+        self.update_at(touch, Instant::now())
+    }
+
+    /// Like `update`, with the time the touch event was received at.
+    pub fn update_at(&mut self, touch: &Touch, now: Instant) -> Vec<Gesture> {
+        let mut gestures = vec![];
+        if touch.finger != 0 {
+            return gestures;
+        }
+        let cell = (touch.position.cx as i32, touch.position.cy as i32);
+        match touch.phase {
+            TouchPhase::Press => {
+                self.track = Some(TouchTrack { from: cell, last: cell, pressed_at: now,
+                                               dragging: false });
+            }
+            TouchPhase::Move => {
+                let track = match self.track.as_mut() {
+                    Some(track) => track,
+                    None => return gestures,
+                };
+                if cell != track.last {
+                    if !track.dragging {
+                        track.dragging = true;
+                        gestures.push(Gesture::DragStart { cx: track.from.0, cy: track.from.1 });
+                    }
+                    gestures.push(Gesture::Drag { from: track.from, to: cell });
+                    track.last = cell;
+                }
+            }
+            TouchPhase::Release => {
+                let track = match self.track.take() {
+                    Some(track) => track,
+                    None => return gestures,
+                };
+                if track.dragging {
+                    gestures.push(Gesture::DragEnd { from: track.from, to: cell });
+                } else if track.from == cell &&
+                    now.duration_since(track.pressed_at) <= self.tap_time {
+                    gestures.push(Gesture::Tap { cx: cell.0, cy: cell.1 });
+                }
+            }
+        }
+        gestures
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Gesture, TouchGestures};
+    use input::{Touch, TouchPhase, TouchPosition};
+    use std::time::{Duration, Instant};
+
+    fn touch(phase: TouchPhase, finger: usize, (cx, cy): (isize, isize)) -> Touch {
+        let position = TouchPosition { x: cx * 8, y: cy * 8, cx, cy, ..Default::default() };
+        Touch { phase, finger, position }
+    }
+
+    #[test]
+    fn taps() {
+        let start = Instant::now();
+        let mut gestures = TouchGestures::new();
+        gestures.update_at(&touch(TouchPhase::Press, 0, (3, 4)), start);
+        assert_eq!(gestures.update_at(&touch(TouchPhase::Release, 0, (3, 4)),
+                                      start + Duration::from_millis(100)),
+                   vec![Gesture::Tap { cx: 3, cy: 4 }]);
+
+        // Too long
+        gestures.update_at(&touch(TouchPhase::Press, 0, (3, 4)), start);
+        assert!(gestures.update_at(&touch(TouchPhase::Release, 0, (3, 4)),
+                                   start + Duration::from_millis(500)).is_empty());
+
+        // Lifted on another cell
+        gestures.update_at(&touch(TouchPhase::Press, 0, (3, 4)), start);
+        assert!(gestures.update_at(&touch(TouchPhase::Release, 0, (4, 4)), start).is_empty());
+    }
+
+    #[test]
+    fn drags() {
+        let mut gestures = TouchGestures::new();
+        gestures.update(&touch(TouchPhase::Press, 0, (1, 1)));
+        assert!(gestures.update(&touch(TouchPhase::Move, 0, (1, 1))).is_empty());
+        assert_eq!(gestures.update(&touch(TouchPhase::Move, 0, (2, 1))),
+                   vec![Gesture::DragStart { cx: 1, cy: 1 },
+                        Gesture::Drag { from: (1, 1), to: (2, 1) }]);
+        assert!(gestures.is_dragging());
+        // A second finger doesn't disturb the drag
+        assert!(gestures.update(&touch(TouchPhase::Press, 1, (9, 9))).is_empty());
+        assert!(gestures.update(&touch(TouchPhase::Move, 1, (8, 9))).is_empty());
+        assert!(gestures.update(&touch(TouchPhase::Release, 1, (8, 9))).is_empty());
+        assert_eq!(gestures.update(&touch(TouchPhase::Release, 0, (3, 1))),
+                   vec![Gesture::DragEnd { from: (1, 1), to: (3, 1) }]);
+        assert!(!gestures.is_dragging());
+    }
+}
@@ -2238,6 +2238,19 @@ extern "C" {
 extern "C" {
     pub fn TCOD_mouse_includes_touch(enable: bool_);
 }
+// Added to the vendored libtcod: reads the finger state of the last touch event.
+extern "C" {
+    pub fn TCOD_mouse_get_touch_finger(finger: *mut ::std::os::raw::c_int,
+                                       x: *mut ::std::os::raw::c_int,
+                                       y: *mut ::std::os::raw::c_int,
+                                       dx: *mut ::std::os::raw::c_int,
+                                       dy: *mut ::std::os::raw::c_int,
+                                       cx: *mut ::std::os::raw::c_int,
+                                       cy: *mut ::std::os::raw::c_int,
+                                       dcx: *mut ::std::os::raw::c_int,
+                                       dcy: *mut ::std::os::raw::c_int)
+                                       -> bool_;
+}
 extern "C" {
     pub fn TCOD_sys_startup();
 }
@@ -2312,16 +2325,19 @@ pub enum TCOD_event_t {
     TCOD_EVENT_FINGER = 224,
     TCOD_EVENT_ANY = 255,
 }
+// These return a combination of `TCOD_event_t` flags (e.g. a finger press together with the
+// emulated mouse press), which isn't necessarily a valid `TCOD_event_t` value.
 extern "C" {
     pub fn TCOD_sys_wait_for_event(eventMask: ::std::os::raw::c_int,
                                    key: *mut TCOD_key_t,
                                    mouse: *mut TCOD_mouse_t, flush: bool_)
-     -> TCOD_event_t;
+     -> ::std::os::raw::c_int;
 }
 extern "C" {
     pub fn TCOD_sys_check_for_event(eventMask: ::std::os::raw::c_int,
                                     key: *mut TCOD_key_t,
-                                    mouse: *mut TCOD_mouse_t) -> TCOD_event_t;
+                                    mouse: *mut TCOD_mouse_t)
+     -> ::std::os::raw::c_int;
 }
 extern "C" {
     pub fn TCOD_sys_create_directory(path: *const ::std::os::raw::c_char)
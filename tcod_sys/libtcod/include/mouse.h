@@ -42,6 +42,8 @@ TCODLIB_API TCOD_mouse_t TCOD_mouse_get_status(void);
 TCODLIB_API bool TCOD_mouse_is_cursor_visible(void);
 TCODLIB_API void TCOD_mouse_move(int x, int y);
 TCODLIB_API void TCOD_mouse_includes_touch(bool enable);
+/* position of the finger of the last touch event. false when there was none or touch input is not compiled in. */
+TCODLIB_API bool TCOD_mouse_get_touch_finger(int *finger, int *x, int *y, int *dx, int *dy, int *cx, int *cy, int *dcx, int *dcy);
 #ifdef __cplusplus
 }
 #endif
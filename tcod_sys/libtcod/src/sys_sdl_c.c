@@ -1034,6 +1034,7 @@ bool TCOD_sys_is_key_pressed(TCOD_keycode_t key) {
 
 #ifdef TCOD_TOUCH_INPUT
 static TCOD_touch_t tcod_touch={0};
+static int tcod_touch_last_idx = -1; /* the finger index of the last touch event. */
 
 static int TCOD_sys_get_touch_finger_index(SDL_FingerID fingerId) {
 	int i;
@@ -1133,6 +1134,8 @@ static TCOD_event_t TCOD_sys_handle_event(SDL_Event *ev,TCOD_event_t eventMask,
 				break;
 			}
 
+			tcod_touch_last_idx = idx;
+
 			/* Count the number of events contributing to an ongoing tracked touch (zeroed above in finger press). */
 			tcod_touch.finger_id = ev->tfinger.fingerId;
 			tcod_touch.nupdates += 1;
@@ -1633,6 +1636,25 @@ void TCOD_mouse_includes_touch(bool enable) {
 #endif
 }
 
+bool TCOD_mouse_get_touch_finger(int *finger, int *x, int *y, int *dx, int *dy, int *cx, int *cy, int *dcx, int *dcy) {
+#ifdef TCOD_TOUCH_INPUT
+	int idx = tcod_touch_last_idx;
+	if (idx == -1) return false;
+	*finger = idx;
+	*x = tcod_touch.coords[idx][0];
+	*y = tcod_touch.coords[idx][1];
+	*dx = tcod_touch.coords_delta[idx][0];
+	*dy = tcod_touch.coords_delta[idx][1];
+	*cx = tcod_touch.consolecoords[idx][0];
+	*cy = tcod_touch.consolecoords[idx][1];
+	*dcx = tcod_touch.consolecoords_delta[idx][0];
+	*dcy = tcod_touch.consolecoords_delta[idx][1];
+	return true;
+#else
+	return false;
+#endif
+}
+
 /*clipboard stuff */
 bool TCOD_sys_clipboard_set(const char *value) {
 	if (!has_startup) { return false; }